@@ -0,0 +1,206 @@
+use anyhow::Result;
+use log::{error, info, warn};
+use std::{env, sync::Arc};
+
+use crate::{
+    cli::{Cli, Command},
+    clients::{dm::DmClient, replay::ReplayTransport, token::TokenClient},
+    config::{load_global_config, Config},
+    events::{ConsoleSink, EventSink, LogSink},
+    i18n,
+    logger::{self, LogFormat},
+    login::DmLogin,
+    runner,
+    selftest,
+    ticket::DmTicket,
+};
+
+// 命令行入口, 按子命令加载配置并运行, 日志需在调用前初始化
+pub async fn run(cli: Cli) -> Result<()> {
+    let command = cli.command();
+    if command == Command::Login {
+        if env::var("QRCODE_PATH").is_err() {
+            env::set_var("QRCODE_PATH", "./qrcode.png");
+        }
+        return DmLogin::new().await?.run().await;
+    }
+
+    let mut config: Config = match load_global_config(cli.config_path()) {
+        Some(conf) => conf,
+        None => {
+            error!("加载配置失败, 退出程序...");
+            return Ok(());
+        }
+    };
+
+    i18n::set_lang(config.lang);
+
+    // 命令行参数优先于配置文件
+    if cli.log_level().is_none() {
+        if let Some(level) = config.log_level {
+            logger::set_level(level.into());
+        }
+    }
+
+    if let Err(e) = config.select_account(cli.account.as_deref()) {
+        error!("{}", e);
+        return Ok(());
+    }
+
+    if let Err(e) = config.expand_tickets() {
+        error!("{}", e);
+        return Ok(());
+    }
+
+    if let Some(dir) = &cli.debug_dump {
+        for account in config.accounts.iter_mut() {
+            account.debug_dump = Some(dir.clone());
+        }
+    }
+
+    match command {
+        Command::ValidateConfig => {
+            for account in config.accounts.iter() {
+                match account.validate() {
+                    Ok(_) => info!("账号:{}, 配置校验通过", account.remark),
+                    Err(e) => error!("{}", e),
+                }
+            }
+            return Ok(());
+        }
+        Command::Selftest => {
+            for account in config.accounts {
+                let remark = account.remark.clone();
+                let checks = selftest::run_self_test(account).await;
+                println!("\n账号:{}", remark);
+                for check in checks.iter() {
+                    println!("\t{}", check);
+                }
+                match selftest::all_passed(&checks) {
+                    true => println!("\t自检通过, 可以开抢"),
+                    false => println!("\t自检未通过, 请根据失败原因检查配置"),
+                }
+            }
+            return Ok(());
+        }
+        Command::Info { json } => {
+            for account in config.accounts {
+                let remark = account.remark.clone();
+                // 单个账号初始化失败不影响其他账号
+                let dm_ticket = match DmTicket::new(account).await {
+                    Ok(dm_ticket) => dm_ticket,
+                    Err(e) => {
+                        error!("账号:{}, 初始化失败, {:?}", remark, e);
+                        continue;
+                    }
+                };
+                match dm_ticket.info().await {
+                    Ok(overview) if json => println!("{}", serde_json::to_string_pretty(&overview)?),
+                    Ok(overview) => println!("\n{}", overview),
+                    Err(e) => error!("账号:{}, 获取门票信息失败, {:?}", remark, e),
+                }
+            }
+            return Ok(());
+        }
+        Command::Replay { dir } => {
+            let mut account = match config.accounts.into_iter().next() {
+                Some(account) => account,
+                None => {
+                    error!("未配置账号, 退出程序...");
+                    return Ok(());
+                }
+            };
+            // 回放时不进行NTP校时
+            account.ntp_server = None;
+            let transport = ReplayTransport::from_dir(&dir)?;
+            let client = DmClient::with_transport(
+                String::new(),
+                &account,
+                Box::new(transport),
+                TokenClient::offline()?,
+            )?;
+            let dm_ticket = DmTicket::with_client(account, client).await?;
+            if let Err(e) = dm_ticket.replay().await {
+                error!("[回放] 失败, {:?}", e);
+            }
+            return Ok(());
+        }
+        Command::Run(overrides) => {
+            // 覆盖后的配置在初始化账号时校验
+            for account in config.accounts.iter_mut() {
+                overrides.apply(account);
+            }
+        }
+        Command::Monitor => {
+            for account in config.accounts.iter_mut() {
+                account.monitor = true;
+            }
+        }
+        _ => {}
+    }
+
+    warn!("\n特别声明: 
+        \n\t1.不得将此项目中任何内容用于违反国家/地区/组织等的法律法规或相关规定的其他用途。
+        \n\t2.此项目涉及的数据由使用的个人或组织自行填写，作者不对数据内容负责，包括但不限于数据的真实性、准确性、合法性。
+        \n\t3.使用本项目所造成的一切后果，与本项目的所有贡献者无关，由使用的个人或组织完全承担。\n\n\n
+        ");
+
+    #[cfg(feature = "tui")]
+    if cli.tui {
+        let outcomes = run_with_dashboard(config).await;
+        print_outcomes(&outcomes);
+        return Ok(());
+    }
+    #[cfg(not(feature = "tui"))]
+    if cli.tui {
+        warn!("未启用tui功能, 忽略--tui参数, 请使用--features tui重新编译");
+    }
+
+    // JSON日志时状态事件也通过日志输出, 避免混入非JSON内容
+    let event_sink: Arc<dyn EventSink> = match cli.log_format {
+        LogFormat::Text => Arc::new(ConsoleSink),
+        LogFormat::Json => Arc::new(LogSink::default()),
+    };
+    let outcomes = runner::run_accounts(config, event_sink).await;
+    print_outcomes(&outcomes);
+
+    Ok(())
+}
+
+// 显示终端仪表盘并运行所有账号, 仪表盘显示期间日志显示在仪表盘中, 避免打乱画面
+#[cfg(feature = "tui")]
+async fn run_with_dashboard(config: Config) -> Vec<runner::AccountOutcome> {
+    let sink = Arc::new(crate::tui::TuiSink::new());
+    let stop = tokio_util::sync::CancellationToken::new();
+    let dashboard = {
+        let sink = sink.clone();
+        let stop = stop.clone();
+        tokio::spawn(async move { sink.run_dashboard(stop).await })
+    };
+
+    {
+        let sink = sink.clone();
+        logger::set_hook(Some(Box::new(move |record: &log::Record| sink.on_log(record))));
+    }
+    let outcomes = runner::run_accounts(config, sink).await;
+    stop.cancel();
+    let res = dashboard.await;
+    logger::set_hook(None);
+
+    match res {
+        Ok(Err(e)) => warn!("终端仪表盘异常退出, 原因:{:?}", e),
+        Err(e) => warn!("终端仪表盘异常退出, 原因:{:?}", e),
+        Ok(Ok(_)) => {}
+    }
+    outcomes
+}
+
+// 输出各账号的运行结果及常见问题提示
+fn print_outcomes(outcomes: &[runner::AccountOutcome]) {
+    println!("\n\n{}", i18n::tr(i18n::Msg::RunResult, &[]));
+    for outcome in outcomes.iter() {
+        println!("\t{}", outcome);
+    }
+
+    println!("\n\n\n{}\n\n\n", i18n::tr(i18n::Msg::OutcomeTips, &[]));
+}
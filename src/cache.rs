@@ -0,0 +1,76 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+// 带过期时间的接口结果缓存, 减少开售前的重复请求
+#[derive(Debug)]
+pub struct TtlCache<T> {
+    // 缓存有效期, 为0则不缓存
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, T)>>,
+}
+
+impl<T: Clone> TtlCache<T> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // 获取未过期的缓存
+    pub fn get(&self, key: &str) -> Option<T> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    // 写入缓存
+    pub fn insert(&self, key: &str, value: T) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), (Instant::now(), value));
+    }
+
+    // 清空缓存
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit_within_ttl() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get("perform"), None);
+        cache.insert("perform", 1);
+        assert_eq!(cache.get("perform"), Some(1));
+
+        cache.clear();
+        assert_eq!(cache.get("perform"), None);
+    }
+
+    #[test]
+    fn miss_after_expiry() {
+        // ttl为0时不缓存
+        let cache = TtlCache::new(Duration::ZERO);
+        cache.insert("perform", 1);
+        assert_eq!(cache.get("perform"), None);
+
+        let cache = TtlCache::new(Duration::from_millis(10));
+        cache.insert("perform", 1);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(cache.get("perform"), None);
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+
+use clap::{ArgAction, Args, Parser, Subcommand};
+use log::LevelFilter;
+
+use crate::{config::Account, logger::LogFormat};
+
+/// 大麦抢票工具
+#[derive(Parser, Debug)]
+#[command(name = "dm-ticket", version)]
+pub struct Cli {
+    /// 配置文件路径
+    #[arg(short, long, global = true, default_value = "config/config.yaml")]
+    pub config: PathBuf,
+
+    /// 兼容旧版的位置参数: dm-ticket config.yaml, 同--config
+    #[arg(hide = true, value_name = "CONFIG", conflicts_with = "config")]
+    pub config_file: Option<PathBuf>,
+
+    /// 只运行指定备注的账号, 默认运行全部账号
+    #[arg(short, long, global = true)]
+    pub account: Option<String>,
+
+    /// 日志格式
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// 输出更详细的日志, -v: debug, -vv: trace, 覆盖配置中的log_level
+    #[arg(short, long, global = true, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// 只输出警告及错误日志, 覆盖配置中的log_level
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// 使用终端仪表盘实时显示各账号的抢购状态, 需使用--features tui编译
+    #[arg(long, global = true)]
+    pub tui: bool,
+
+    /// 保存每次API请求的参数及原始响应到该目录, 用于排查接口返回异常
+    #[arg(long, global = true)]
+    pub debug_dump: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// 抢票, 默认命令
+    Run(RunOverrides),
+
+    /// 监控库存, 有票时发送通知, 不下单
+    Monitor,
+
+    /// 查看门票的场次/票档列表
+    Info {
+        /// 以JSON格式输出
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// 扫码登录, 获取cookie
+    Login,
+
+    /// 校验配置文件
+    ValidateConfig,
+
+    /// 开抢前自检, 检查cookie/token/代理/门票ID是否可用, 不下单
+    Selftest,
+
+    /// 回放--debug-dump保存的原始响应, 不发送网络请求, 用于复现接口返回异常
+    Replay {
+        /// 原始响应所在目录
+        dir: PathBuf,
+    },
+}
+
+/// 本次运行覆盖配置文件中的参数
+#[derive(Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunOverrides {
+    /// 轮询间隔, 覆盖配置中的interval, 单位毫秒
+    #[arg(long)]
+    pub interval: Option<u64>,
+
+    /// 提前发包时间, 覆盖配置中的early_submit_time, 单位毫秒
+    #[arg(long)]
+    pub early_submit_time: Option<i64>,
+
+    /// 抢票重试次数, 覆盖配置中的retry_times
+    #[arg(long)]
+    pub retry_times: Option<u8>,
+}
+
+impl RunOverrides {
+    // 覆盖账号配置
+    pub fn apply(&self, account: &mut Account) {
+        if let Some(interval) = self.interval {
+            account.interval = interval;
+        }
+        if let Some(early_submit_time) = self.early_submit_time {
+            account.early_submit_time = early_submit_time;
+        }
+        if let Some(retry_times) = self.retry_times {
+            account.retry_times = retry_times;
+        }
+    }
+}
+
+impl Cli {
+    // 子命令, 未指定时为run
+    pub fn command(&self) -> Command {
+        self.command
+            .clone()
+            .unwrap_or_else(|| Command::Run(RunOverrides::default()))
+    }
+
+    // 配置文件路径, 优先使用位置参数
+    pub fn config_path(&self) -> &PathBuf {
+        self.config_file.as_ref().unwrap_or(&self.config)
+    }
+
+    // 命令行指定的日志级别, 未指定时为None
+    pub fn log_level(&self) -> Option<LevelFilter> {
+        match (self.quiet, self.verbose) {
+            (true, _) => Some(LevelFilter::Warn),
+            (false, 0) => None,
+            (false, 1) => Some(LevelFilter::Debug),
+            (false, _) => Some(LevelFilter::Trace),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positional_config() {
+        let cli = Cli::try_parse_from(["dm-ticket", "config.yaml"]).unwrap();
+        assert_eq!(cli.config_path(), &PathBuf::from("config.yaml"));
+        assert_eq!(cli.command(), Command::Run(RunOverrides::default()));
+    }
+
+    #[test]
+    fn config_option_and_subcommand() {
+        let cli = Cli::try_parse_from(["dm-ticket", "info", "--config", "a.yaml"]).unwrap();
+        assert_eq!(cli.config_path(), &PathBuf::from("a.yaml"));
+        assert_eq!(cli.command(), Command::Info { json: false });
+
+        let cli = Cli::try_parse_from(["dm-ticket"]).unwrap();
+        assert_eq!(cli.config_path(), &PathBuf::from("config/config.yaml"));
+    }
+}
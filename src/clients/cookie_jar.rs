@@ -0,0 +1,83 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use log::{debug, warn};
+use reqwest_cookie_store::{CookieStore, CookieStoreMutex};
+
+use crate::models::DmToken;
+
+// 保存的token剩余有效期小于该值时重新获取, 单位毫秒
+const TOKEN_EXPIRE_MARGIN_MS: i64 = 60 * 1000;
+
+// 持久化的cookie存储, 保存token等服务器下发的cookie, 重启后复用
+#[derive(Debug, Clone)]
+pub struct PersistentCookieStore {
+    pub store: Arc<CookieStoreMutex>,
+    pub path: PathBuf,
+}
+
+impl PersistentCookieStore {
+    // 从文件加载, 文件不存在或解析失败时使用空的cookie存储
+    pub fn load(path: &Path) -> Self {
+        let store = match File::open(path) {
+            Ok(file) => CookieStore::load_json(BufReader::new(file)).unwrap_or_else(|e| {
+                warn!("加载cookie存储:{:?}失败, 使用空的cookie存储, 原因:{}", path, e);
+                CookieStore::default()
+            }),
+            Err(_) => CookieStore::default(),
+        };
+        Self {
+            store: Arc::new(CookieStoreMutex::new(store)),
+            path: path.to_path_buf(),
+        }
+    }
+
+    // 保存到文件, 只保存未过期的持久cookie
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| anyhow!("创建目录:{:?}失败, {}", dir, e))?;
+        }
+        let mut writer = BufWriter::new(
+            File::create(&self.path)
+                .map_err(|e| anyhow!("创建文件:{:?}失败, {}", self.path, e))?,
+        );
+        self.store
+            .lock()
+            .unwrap()
+            .save_json(&mut writer)
+            .map_err(|e| anyhow!("保存cookie存储:{:?}失败, {}", self.path, e))?;
+        debug!("cookie存储已保存至:{:?}", self.path);
+        Ok(())
+    }
+
+    // 读取保存的_m_h5_tk/_m_h5_tk_enc, 不存在或即将过期时返回None
+    pub fn token(&self) -> Option<DmToken> {
+        let store = self.store.lock().unwrap();
+        let find = |name: &str| {
+            store
+                .iter_unexpired()
+                .find(|cookie| cookie.name() == name)
+                .map(|cookie| cookie.value().to_string())
+        };
+        let token_with_time = find("_m_h5_tk")?;
+        let enc_token = find("_m_h5_tk_enc")?;
+
+        // _m_h5_tk格式为: token_过期时间戳(毫秒)
+        let (token, expire_at) = token_with_time.split_once('_')?;
+        let expire_at = expire_at.parse::<i64>().ok()?;
+        if expire_at - Local::now().timestamp_millis() < TOKEN_EXPIRE_MARGIN_MS {
+            return None;
+        }
+        Some(DmToken {
+            token: token.to_string(),
+            token_with_time: token_with_time.clone(),
+            enc_token,
+        })
+    }
+}
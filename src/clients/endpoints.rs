@@ -0,0 +1,134 @@
+use crate::config::ApiVersions;
+
+// 默认API域名
+pub const DEFAULT_BASE_DOMAIN: &str = "mtop.damai.cn";
+
+// 默认appKey
+pub const DEFAULT_APP_KEY: &str = "12574478";
+
+// 各接口的默认版本号
+pub const DEFAULT_USER_INFO_VERSION: &str = "1.0";
+pub const DEFAULT_TICKET_INFO_VERSION: &str = "1.2";
+pub const DEFAULT_PERFORM_INFO_VERSION: &str = "2.0";
+pub const DEFAULT_BUILD_ORDER_VERSION: &str = "4.0";
+pub const DEFAULT_SUBMIT_ORDER_VERSION: &str = "4.0";
+
+// 从请求地址中解析接口版本号, 如: https://mtop.damai.cn/h5/mtop.trade.order.build.h5/4.0/ => 4.0
+pub fn api_version(url: &str) -> Option<&str> {
+    let mut parts = url.split('/');
+    parts.find(|part| part.starts_with("mtop."))?;
+    parts
+        .next()
+        .map(|version| version.trim_end_matches('?'))
+        .filter(|version| !version.is_empty())
+}
+
+// 大麦API地址, 由域名拼接
+#[derive(Debug, Clone)]
+pub struct Endpoints {
+    // 根地址, 如: https://mtop.damai.cn/
+    base_url: String,
+
+    // 各接口版本号
+    versions: ApiVersions,
+}
+
+impl Endpoints {
+    // 支持域名或完整地址, 如: mtop.damai.cn, http://127.0.0.1:8080
+    pub fn new(base_domain: &str, versions: &ApiVersions) -> Self {
+        let base_domain = base_domain.trim().trim_end_matches('/');
+        let base_url = match base_domain.starts_with("http://") || base_domain.starts_with("https://") {
+            true => format!("{}/", base_domain),
+            false => format!("https://{}/", base_domain),
+        };
+        Self {
+            base_url,
+            versions: versions.clone(),
+        }
+    }
+
+    // 根地址, 用于origin/referer
+    pub fn base(&self) -> &str {
+        &self.base_url
+    }
+
+    fn h5(&self, path: &str) -> String {
+        format!("{}h5/{}", self.base_url, path)
+    }
+
+    // 获取_m_h5_tk
+    pub fn token(&self) -> String {
+        self.h5("mtop.damai.wireless.search.broadcast.list/1.0/?")
+    }
+
+    // 用户信息
+    pub fn user_info(&self) -> String {
+        self.h5(&format!("mtop.damai.wireless.user.session.transform/{}/", self.versions.user_info))
+    }
+
+    // 门票信息
+    pub fn ticket_info(&self) -> String {
+        self.h5(&format!("mtop.alibaba.damai.detail.getdetail/{}", self.versions.ticket_info))
+    }
+
+    // 场次/票档信息
+    pub fn perform_info(&self) -> String {
+        self.h5(&format!("mtop.alibaba.detail.subpage.getdetail/{}/", self.versions.perform_info))
+    }
+
+    // 生成订单
+    pub fn build_order(&self) -> String {
+        self.h5(&format!("mtop.trade.order.build.h5/{}/?", self.versions.build_order))
+    }
+
+    // 提交订单
+    pub fn submit_order(&self) -> String {
+        self.h5(&format!("mtop.trade.order.create.h5/{}/", self.versions.submit_order))
+    }
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_DOMAIN, &ApiVersions::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_from_base_domain() {
+        assert_eq!(
+            Endpoints::default().build_order(),
+            "https://mtop.damai.cn/h5/mtop.trade.order.build.h5/4.0/?"
+        );
+        // 支持完整地址, 便于指向测试服务器
+        let endpoints = Endpoints::new("http://127.0.0.1:8080/", &ApiVersions::default());
+        assert_eq!(endpoints.base(), "http://127.0.0.1:8080/");
+        assert_eq!(
+            endpoints.submit_order(),
+            "http://127.0.0.1:8080/h5/mtop.trade.order.create.h5/4.0/"
+        );
+    }
+
+    #[test]
+    fn configured_api_versions() {
+        let versions = ApiVersions {
+            build_order: "5.0".to_string(),
+            ..ApiVersions::default()
+        };
+        let endpoints = Endpoints::new(DEFAULT_BASE_DOMAIN, &versions);
+
+        assert_eq!(api_version(&endpoints.build_order()), Some("5.0"));
+        assert_eq!(
+            api_version(&endpoints.submit_order()),
+            Some(DEFAULT_SUBMIT_ORDER_VERSION)
+        );
+        assert_eq!(
+            api_version(&endpoints.ticket_info()),
+            Some(DEFAULT_TICKET_INFO_VERSION)
+        );
+        assert_eq!(api_version("https://mtop.damai.cn/"), None);
+    }
+}
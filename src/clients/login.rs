@@ -1,12 +1,12 @@
 use std::env;
 
-use crate::clients::token::TokenClient;
+use crate::clients::{bx_ua, token::TokenClient};
 use crate::models::qrcode::{
     QrCodeLoginGetResForm, QrCodeLoginGetResParams, QrCodeLoginStatusData, QrcodeContentGetParams,
     QrcodeData,
 };
 use crate::models::DmLoginRes;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use fast_qr::{QRBuilder, QRCode};
 
 use reqwest::{
@@ -22,12 +22,15 @@ pub struct LoginClient {
     pub token_client: TokenClient,
     pub client: Client,
     pub bx_token: String,
+    // bx-ua种子, 每个请求按请求数据及时间戳生成bx-ua
+    pub bx_ua_seed: String,
 }
 
 impl LoginClient {
     pub async fn new() -> Result<Self> {
         let token_client = TokenClient::new()?;
         let bx_token = token_client.get_bx_token().await?;
+        let bx_ua_seed = token_client.get_bx_ua().await?;
 
         let mut headers = HeaderMap::new();
         headers.append("user-agent", HeaderValue::from_str("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/113.0.0.0 Safari/537.36")?);
@@ -47,12 +50,18 @@ impl LoginClient {
             token_client,
             client,
             bx_token,
+            bx_ua_seed,
         })
     }
 
     pub async fn request(&self, url: &str, mut params: Value, data: Value) -> Result<DmLoginRes> {
         params["bx-umidtoken"] = self.bx_token.clone().into();
-        params["bx-ua"] = self.token_client.get_bx_ua().await?.into();
+        params["bx-ua"] = bx_ua(
+            &self.bx_ua_seed,
+            params["t"].as_str().unwrap_or_default(),
+            &data.to_string(),
+        )
+        .into();
 
         let response = self
             .client
@@ -79,7 +88,8 @@ impl LoginClient {
 
     // 获取二维码
     pub async fn get_qrcode(&self, qrcode_content: String) -> Result<QRCode> {
-        let qrcode_path = env::var("QRCODE_PATH").unwrap();
+        let qrcode_path =
+            env::var("QRCODE_PATH").map_err(|_| anyhow!("未设置环境变量QRCODE_PATH..."))?;
         let url = format!(
             "https://gcodex.alicdn.com/qrcode.do?biz_code=havana&size=140&content={}",
             urlencoding::encode(&qrcode_content)
@@ -101,9 +111,14 @@ impl LoginClient {
         let mut img = rqrr::PreparedImage::prepare(img);
 
         let grids = img.detect_grids();
-        let (_, content) = grids[0].decode()?;
-
-        let qrcode = QRBuilder::new(content).build().unwrap();
+        let (_, content) = grids
+            .first()
+            .ok_or_else(|| anyhow!("未识别到登录二维码..."))?
+            .decode()?;
+
+        let qrcode = QRBuilder::new(content)
+            .build()
+            .map_err(|e| anyhow!("生成登录二维码失败, {:?}", e))?;
 
         let _ = fs::remove_file(qrcode_path).await;
 
@@ -1,3 +1,67 @@
+pub mod cookie_jar;
 pub mod dm;
+pub mod endpoints;
 pub mod login;
+pub mod replay;
 pub mod token;
+pub mod transport;
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+// 内置的移动端User-Agent列表, 未配置user_agent时按账号选择其中一个
+pub const USER_AGENTS: &[&str] = &[
+    "Mozilla/5.0 (iPhone; CPU iPhone OS 13_2_3 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/13.0.3",
+    "Mozilla/5.0 (iPhone; CPU iPhone OS 16_6 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.6 Mobile/15E148 Safari/604.1",
+    "Mozilla/5.0 (iPhone; CPU iPhone OS 17_1_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1.2 Mobile/15E148 Safari/604.1",
+    "Mozilla/5.0 (Linux; Android 13; SM-S9180) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Mobile Safari/537.36",
+    "Mozilla/5.0 (Linux; Android 12; M2102K1C) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/112.0.5615.136 Mobile Safari/537.36",
+    "Mozilla/5.0 (Linux; Android 13; PHB110) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.5735.196 Mobile Safari/537.36",
+];
+
+// 根据seed(如账号备注)选择User-Agent, 同一seed每次运行选择相同的User-Agent
+pub fn pick_user_agent(seed: &str) -> &'static str {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    USER_AGENTS[(hasher.finish() % USER_AGENTS.len() as u64) as usize]
+}
+
+// 计算mtop接口签名: md5(token&t&appKey&data)
+pub fn sign_request(token: &str, t: &str, app_key: &str, data: &str) -> String {
+    format!("{:?}", md5::compute(format!("{}&{}&{}&{}", token, t, app_key, data)))
+}
+
+// 根据token-server返回的bx-ua种子、时间戳及签名生成本次请求的bx-ua, 请求数据或时间戳不同时生成的值不同
+pub fn bx_ua(seed: &str, t: &str, sign: &str) -> String {
+    format!("{}{:?}", seed, md5::compute(format!("{}&{}&{}", seed, t, sign)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_request_md5() {
+        let sign = sign_request(
+            "abcdef0123456789",
+            "1700000000000",
+            "12574478",
+            r#"{"itemId":"123456"}"#,
+        );
+        assert_eq!(sign, "7161620c732fa374c62bbd4aea7c91c6");
+    }
+
+    #[test]
+    fn bx_ua_varies_with_payload_and_timestamp() {
+        let t = "1700000000000";
+        let sign_a = sign_request("token", t, "12574478", r#"{"itemId":"1"}"#);
+        let sign_b = sign_request("token", t, "12574478", r#"{"itemId":"2"}"#);
+
+        assert_ne!(bx_ua("seed", t, &sign_a), bx_ua("seed", t, &sign_b));
+        assert_ne!(bx_ua("seed", t, &sign_a), bx_ua("seed", "1700000000001", &sign_a));
+        // 相同请求生成的值相同
+        assert_eq!(bx_ua("seed", t, &sign_a), bx_ua("seed", t, &sign_a));
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    path::Path,
+    sync::Mutex,
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use log::debug;
+use serde_json::Value;
+
+use super::transport::{HttpTransport, TransportResponse};
+use crate::dump::{api_name, ResponseDump};
+
+// 回放传输层, 按API依次返回--debug-dump保存的原始响应, 不发送网络请求
+#[derive(Debug)]
+pub struct ReplayTransport {
+    responses: Mutex<HashMap<String, VecDeque<ResponseDump>>>,
+}
+
+impl ReplayTransport {
+    pub fn new(dumps: Vec<ResponseDump>) -> Self {
+        let mut responses: HashMap<String, VecDeque<ResponseDump>> = HashMap::new();
+        for dump in dumps {
+            responses.entry(dump.api.clone()).or_default().push_back(dump);
+        }
+        Self {
+            responses: Mutex::new(responses),
+        }
+    }
+
+    // 读取目录下保存的原始响应
+    pub fn from_dir(dir: &Path) -> Result<Self> {
+        let dumps = ResponseDump::load_dir(dir)?;
+        if dumps.is_empty() {
+            return Err(anyhow!("目录:{:?}中没有保存的原始响应", dir));
+        }
+        Ok(Self::new(dumps))
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReplayTransport {
+    async fn post(
+        &self,
+        url: &str,
+        _cookie: &str,
+        _query: &Value,
+        _form: &Value,
+    ) -> Result<TransportResponse> {
+        let api = api_name(url);
+        let dump = self
+            .responses
+            .lock()
+            .unwrap()
+            .get_mut(api)
+            .and_then(|queue| queue.pop_front())
+            .ok_or_else(|| anyhow!("回放数据中没有API:{}的响应", api))?;
+        debug!("回放API:{}, 保存时间:{}", api, dump.time);
+
+        Ok(TransportResponse {
+            status: dump.status,
+            retry_after: None,
+            date: None,
+            body: dump.body,
+        })
+    }
+}
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use log::{debug, warn};
 use reqwest::Client;
 use serde_json::{json, Value};
@@ -12,7 +12,8 @@ const SYSTEM_ERROR_CODE: u16 = 500;
 
 #[derive(Debug)]
 pub struct TokenClient {
-    pub client: Client,
+    // 离线模式下为None, 不请求token-server, 用于回放
+    pub client: Option<Client>,
 }
 
 impl TokenClient {
@@ -20,19 +21,36 @@ impl TokenClient {
         let client = reqwest::Client::builder()
             .timeout(Duration::from_secs(3))
             .build()?;
-        Ok(Self { client })
+        Ok(Self {
+            client: Some(client),
+        })
+    }
+
+    // 离线模式, 返回占位值
+    pub fn offline() -> Result<Self> {
+        Ok(Self { client: None })
+    }
+
+    // 是否为离线模式
+    pub fn is_offline(&self) -> bool {
+        self.client.is_none()
     }
 
     // Get value from api.
     pub async fn get_value(&self, key: &str) -> Result<String> {
-        let url = env::var("TOKEN_SERVER_URL").unwrap();
+        let client = match &self.client {
+            Some(client) => client,
+            None => return Ok(format!("offline_{}", key)),
+        };
+
+        let url = env::var("TOKEN_SERVER_URL")
+            .map_err(|_| anyhow!("未设置环境变量TOKEN_SERVER_URL, 请配置token-server地址..."))?;
 
         let params = json!({
             "key": key,
         });
 
-        let data = self
-            .client
+        let data = client
             .get(url)
             .query(&params)
             .send()
@@ -44,11 +62,14 @@ impl TokenClient {
             .get("code")
             .unwrap_or(&SYSTEM_ERROR_CODE.into())
             .as_u64()
-            .unwrap();
+            .ok_or_else(|| anyhow!("Unexpected response of {}: {}", key, data))?;
 
         Ok(match code {
             SUCCESS_CODE => {
-                let value = data["data"]["value"].as_str().unwrap().to_string();
+                let value = data["data"]["value"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("Unexpected response of {}: {}", key, data))?
+                    .to_string();
                 debug!("Get {}:{}", key, value);
                 value
             }
@@ -59,23 +80,33 @@ impl TokenClient {
         })
     }
 
-    // Get bx ua.
+    // Get bx ua seed, 每个请求的bx-ua由clients::bx_ua根据请求数据生成.
     pub async fn get_bx_ua(&self) -> Result<String> {
         let start = Instant::now();
         let bx_ua = self.get_value("bx_ua").await?;
-        warn!("获取bx_ua: {:?}, 花费时间:{:?}", bx_ua, start.elapsed());
+        if bx_ua.is_empty() {
+            return Err(anyhow!("获取bx_ua失败, 请检查token-server是否正常运行..."));
+        }
+        debug!("获取bx_ua: {:?}, 花费时间:{:?}", bx_ua, start.elapsed());
         Ok(bx_ua)
     }
 
     // Get bx token.
     pub async fn get_bx_token(&self) -> Result<String> {
         let start = Instant::now();
-        let bx_token = self.get_value("bx_token").await?;
-        warn!(
-            "获取bx_token: {:?}, 花费时间:{:?}",
-            bx_token,
-            start.elapsed()
-        );
+        let bx_token = match self.get_value("bx_token").await {
+            Ok(value) => value,
+            // 网络波动, 重试一次
+            Err(e) if e.is::<reqwest::Error>() => {
+                warn!("获取bx_token失败:{:?}, 重试中...", e);
+                self.get_value("bx_token").await?
+            }
+            Err(e) => return Err(e),
+        };
+        if bx_token.is_empty() {
+            return Err(anyhow!("获取bx_token失败, 请检查token-server是否正常运行..."));
+        }
+        debug!("获取bx_token: {:?}, 花费时间:{:?}", bx_token, start.elapsed());
         Ok(bx_token)
     }
 }
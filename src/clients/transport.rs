@@ -0,0 +1,164 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Debug},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::{
+    cookie::CookieStore,
+    header::{HeaderName, DATE, RETRY_AFTER},
+    Client, Url,
+};
+use serde_json::Value;
+
+// 请求响应
+#[derive(Debug, Clone)]
+pub struct TransportResponse {
+    // HTTP状态码
+    pub status: u16,
+
+    // 响应头Retry-After, 限流(429)时服务器建议的等待时间
+    pub retry_after: Option<String>,
+
+    // 响应头Date, 用于计算服务器时间偏移量
+    pub date: Option<String>,
+
+    // 响应内容
+    pub body: String,
+}
+
+// HTTP传输层, 可替换为模拟实现用于测试
+#[async_trait]
+pub trait HttpTransport: Send + Sync + Debug {
+    // 发送POST请求, query为URL参数, form为表单数据
+    async fn post(
+        &self,
+        url: &str,
+        cookie: &str,
+        query: &Value,
+        form: &Value,
+    ) -> Result<TransportResponse>;
+}
+
+// 合并请求指定的cookie与cookie存储中的cookie, 同名时使用请求指定的cookie
+// 请求指定了cookie头时reqwest不会再添加cookie存储中的cookie, 需手动合并
+pub fn merge_cookies(cookie: &str, stored: Option<&str>) -> String {
+    let split = |cookie: &str| -> Vec<String> {
+        cookie
+            .split(';')
+            .map(|pair| pair.trim())
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| pair.to_string())
+            .collect()
+    };
+    let name = |pair: &str| pair.split('=').next().unwrap_or_default().trim().to_string();
+
+    let mut pairs = split(cookie);
+    let names: HashSet<String> = pairs.iter().map(|pair| name(pair)).collect();
+    for pair in split(stored.unwrap_or_default()) {
+        if !names.contains(&name(&pair)) {
+            pairs.push(pair);
+        }
+    }
+    pairs.join("; ")
+}
+
+// 读取cookie存储中发送到url的cookie, 并与请求指定的cookie合并
+pub fn with_stored_cookies(cookies: &dyn CookieStore, url: &str, cookie: &str) -> String {
+    let stored = Url::parse(url).ok().and_then(|url| cookies.cookies(&url));
+    merge_cookies(cookie, stored.as_ref().and_then(|value| value.to_str().ok()))
+}
+
+// 基于reqwest的HTTP传输层
+pub struct ReqwestTransport {
+    client: Client,
+    // 与client共用的cookie存储, 用于合并服务器下发的cookie
+    cookies: Arc<dyn CookieStore>,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: Client, cookies: Arc<dyn CookieStore>) -> Self {
+        Self { client, cookies }
+    }
+}
+
+impl Debug for ReqwestTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReqwestTransport")
+            .field("client", &self.client)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn post(
+        &self,
+        url: &str,
+        cookie: &str,
+        query: &Value,
+        form: &Value,
+    ) -> Result<TransportResponse> {
+        let response = self
+            .client
+            .post(url)
+            .header("cookie", with_stored_cookies(self.cookies.as_ref(), url, cookie))
+            .query(query)
+            .form(form)
+            .send()
+            .await?;
+
+        let header = |name: HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+        let status = response.status().as_u16();
+        let date = header(DATE);
+        let retry_after = header(RETRY_AFTER);
+        let body = response.text().await?;
+
+        Ok(TransportResponse {
+            status,
+            retry_after,
+            date,
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use reqwest::cookie::Jar;
+    use serde_json::json;
+
+    #[test]
+    fn merge_cookies_prefers_request_cookie() {
+        assert_eq!(
+            merge_cookies("a=1;_m_h5_tk=new;", Some("_m_h5_tk=old; b=2")),
+            "a=1; _m_h5_tk=new; b=2"
+        );
+        assert_eq!(merge_cookies("a=1", None), "a=1");
+    }
+
+    #[tokio::test]
+    async fn stored_cookie_sent_on_next_request() {
+        let jar = Arc::new(Jar::default());
+        let client = Client::builder().cookie_provider(jar.clone()).build().unwrap();
+        let transport = ReqwestTransport::new(client, jar);
+
+        let (url, _request) = testing::serve_once_with_headers("Set-Cookie: cna=abc; Path=/\r\n", "{}");
+        transport.post(&url, "a=1", &json!({}), &json!({})).await.unwrap();
+
+        let (url, request) = testing::serve_once("{}");
+        transport.post(&url, "a=1", &json!({}), &json!({})).await.unwrap();
+        let request = request.recv().unwrap();
+        assert!(request.contains("cookie: a=1; cna=abc"), "{}", request);
+    }
+}
@@ -1,7 +1,26 @@
+use anyhow::{anyhow, Result};
+use crate::{
+    clients::{
+        endpoints::{
+            DEFAULT_APP_KEY, DEFAULT_BASE_DOMAIN, DEFAULT_BUILD_ORDER_VERSION,
+            DEFAULT_PERFORM_INFO_VERSION, DEFAULT_SUBMIT_ORDER_VERSION,
+            DEFAULT_TICKET_INFO_VERSION, DEFAULT_USER_INFO_VERSION,
+        },
+        pick_user_agent,
+    },
+    i18n::Lang,
+    logger::LogLevel,
+};
 use log::error;
-use std::env;
+use regex::Regex;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::path::{Path, PathBuf};
 use schemars::schema::RootSchema;
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use serde::{
+    de::{self, DeserializeOwned},
+    Deserialize, Deserializer, Serialize,
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Ticket {
@@ -14,9 +33,29 @@ pub struct Ticket {
     // 场次序号
     pub sessions: usize,
 
+    // 场次优先级, 当前场次无法购买时依次尝试下一场次。默认为空, 只购买sessions场次
+    #[serde(default = "default_session_priority")]
+    pub session_priority: Vec<usize>,
+
+    // 场次日期, 如: 2024-06-15。配置后按日期匹配场次, 忽略sessions/session_priority
+    #[serde(default)]
+    pub session_date: Option<String>,
+
     // 票挡序号
     pub grade: usize,
 
+    // 票档优先级, 当前票档购买失败时依次尝试下一票档。默认为空, 只购买grade票档
+    #[serde(default = "default_grade_priority")]
+    pub grade_priority: Vec<usize>,
+
+    // 跨票档凑票, 单个票档不足num张时按票档优先级分多个订单购买, 直到凑够num张或所有票档无票
+    #[serde(default)]
+    pub split_across_grades: bool,
+
+    // 票档价格上限, 配置后自动选择不超过该价格的最高价票档, 忽略grade/grade_priority
+    #[serde(default)]
+    pub max_price: Option<f64>,
+
     // 优先购时长
     #[serde(default = "default_priority_purchase_time")]
     pub priority_purchase_time: i64,
@@ -27,6 +66,44 @@ pub struct Ticket {
     // 实名人选择
     #[serde(default = "default_real_names")]
     pub real_names: Vec<usize>,
+
+    // 选择全部实名观演人, 与real_names不能同时配置
+    #[serde(default)]
+    pub all_real_names: bool,
+
+    // 选择的实名观演人数量, 用于观演人数与购票数量不一致的演出, 配置了real_names时选择其中前viewer_count位。默认与num相同
+    #[serde(default)]
+    pub viewer_count: Option<usize>,
+}
+
+// 未配置ticket时的占位配置, 配置了tickets时会被替换
+fn default_ticket() -> Ticket {
+    Ticket {
+        id: String::new(),
+        num: 1,
+        sessions: 1,
+        session_priority: default_session_priority(),
+        session_date: None,
+        grade: 1,
+        grade_priority: default_grade_priority(),
+        split_across_grades: false,
+        max_price: None,
+        priority_purchase_time: default_priority_purchase_time(),
+        pick_up_leaks: PickUpLeaks::default(),
+        real_names: default_real_names(),
+        all_real_names: false,
+        viewer_count: None,
+    }
+}
+
+// 场次优先级, 默认为空, 只购买sessions场次
+fn default_session_priority() -> Vec<usize> {
+    vec![]
+}
+
+// 票档优先级, 默认为空, 只购买grade票档
+fn default_grade_priority() -> Vec<usize> {
+    vec![]
 }
 
 // 实名人, 默认自动选择前ticket->num位。
@@ -42,20 +119,59 @@ fn default_priority_purchase_time() -> i64 {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Account {
     // .damai.cn cookie
+    #[serde(default)]
     pub cookie: String,
+
+    // cookie文件路径, 与cookie二选一
+    #[serde(default)]
+    pub cookie_file: Option<PathBuf>,
+
     // 账号备注
     pub remark: String,
     // 门票配置
+    #[serde(default = "default_ticket")]
     pub ticket: Ticket,
 
+    // 多个门票配置, 用于同时抢购多场演出, 与ticket二选一
+    #[serde(default)]
+    pub tickets: Vec<Ticket>,
+
+    // 配置了多个门票时, 任一门票抢购成功后停止该账号的其他门票
+    #[serde(default)]
+    pub stop_other_tickets: bool,
+
+    // 拆分多个门票前的账号备注, 用于stop_other_tickets
+    #[serde(skip)]
+    pub group: String,
+
     // 轮询判断开抢时间
     #[serde(default = "default_interval")]
     pub interval: u64,
 
+    // 距离开抢不足该时间时切换为每1毫秒检查一次, 单位毫秒
+    #[serde(default = "default_precise_wait_threshold")]
+    pub precise_wait_threshold: i64,
+
+    // 最后阶段的等待方式, sleep: 每1毫秒休眠检查, spin: 忙等待
+    #[serde(default)]
+    pub spin_mode: SpinMode,
+
+    // spin模式下, 距离发包不足该时间时忙等待, 单位毫秒
+    #[serde(default = "default_final_spin_ms")]
+    pub final_spin_ms: i64,
+
+    // 开抢前多久预热连接, 单位毫秒, 0为不预热
+    #[serde(default = "default_warmup_lead_time")]
+    pub warmup_lead_time: i64,
+
     // 提前发送数据包的时间
     #[serde(default = "default_early_submit_time")]
     pub early_submit_time: i64,
 
+    // 根据测量的请求耗时自动设置early_submit_time, 预热后测量, 未启用预热时开始等待即测量, 关闭时使用配置值
+    #[serde(default)]
+    pub auto_early_submit_time: bool,
+
     // 自定义发送数据包的时间
     #[serde(default = "default_request_time")]
     pub request_time: i64,
@@ -68,14 +184,646 @@ pub struct Account {
     #[serde(default = "default_retry_interval")]
     pub retry_interval: u64,
 
+    // 重试间隔随机抖动, 每次等待重试间隔 ± 0~retry_jitter_ms毫秒, 默认0不抖动
+    #[serde(default)]
+    pub retry_jitter_ms: u64,
+
+    // 重试间隔策略
+    #[serde(default)]
+    pub retry_strategy: RetryStrategy,
+
     // 生成订单/跟提交订单直接的间隔
     #[serde(default = "default_wait_for_submit_time")]
     pub wait_for_submit_time: u64,
+
+    // 生成订单后随机等待时间的下限/上限, 未配置时使用wait_for_submit_time
+    #[serde(default)]
+    pub wait_for_submit_time_min: Option<u64>,
+
+    #[serde(default)]
+    pub wait_for_submit_time_max: Option<u64>,
+
+    // 浏览器控制台日志文件路径, 不配置则不读取
+    #[serde(default)]
+    pub console_log: Option<PathBuf>,
+
+    // API域名, 国际版或区域CDN可修改, 如: mtop.damai.cn
+    #[serde(default = "default_base_domain")]
+    pub base_domain: String,
+
+    // appKey及各接口版本号, 大麦升级接口时可修改
+    #[serde(default)]
+    pub api: ApiConfig,
+
+    // 代理地址, 支持http://和socks5://
+    #[serde(default)]
+    pub proxy: Option<String>,
+
+    // 发送请求使用的本机IP地址, 多IP服务器可指定出口IP, 如: 192.168.1.100
+    #[serde(default)]
+    pub local_address: Option<IpAddr>,
+
+    // 请求使用的User-Agent, 未配置时从内置列表中按账号备注选择
+    #[serde(default)]
+    pub user_agent: Option<String>,
+
+    // cookie存储目录, 配置后按账号保存服务器下发的cookie(token等), 重启后复用
+    #[serde(default)]
+    pub cookie_store_dir: Option<PathBuf>,
+
+    // 单个请求超时时间, 单位毫秒
+    #[serde(default = "default_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+
+    // 门票/场次信息缓存时间, 单位秒, 为0则不缓存。查询库存等对时效敏感的请求不使用缓存
+    #[serde(default = "default_info_cache_ttl_secs")]
+    pub info_cache_ttl_secs: u64,
+
+    // 并发抢购数, 同时发起多个生成/提交订单流程, 默认1
+    #[serde(default = "default_parallel_builds")]
+    pub parallel_builds: usize,
+
+    // 同时进行中的生成订单请求数上限, 未配置时与parallel_builds一致
+    #[serde(default)]
+    pub max_inflight: Option<usize>,
+
+    // 并发抢购时各流程的错开时间, 单位毫秒
+    #[serde(default = "default_parallel_stagger")]
+    pub parallel_stagger: u64,
+
+    // 提交订单失败时, 使用同一订单重试提交的次数
+    #[serde(default = "default_submit_retry_times")]
+    pub submit_retry_times: u8,
+
+    // 请求超时/连接失败时的重试次数
+    #[serde(default = "default_transport_retry_times")]
+    pub transport_retry_times: u8,
+
+    // NTP服务器地址, 配置后使用NTP校准本地时间
+    #[serde(default)]
+    pub ntp_server: Option<String>,
+
+    // 通知配置
+    #[serde(default)]
+    pub notify: NotifyConfig,
+
+    // 提交订单的渠道参数, 大麦调整渠道标识时可修改
+    #[serde(default)]
+    pub order_feature: OrderFeature,
+
+    // 下单记录文件, 抢购成功后以JSON Lines格式追加订单号/支付链接
+    #[serde(default = "default_order_record_file")]
+    pub order_record_file: PathBuf,
+
+    // 定时启动, 到达该时间后才开始获取门票信息并等待开抢, 如: 2024-06-15 11:50:00
+    #[serde(default, deserialize_with = "deserialize_local_datetime")]
+    pub scheduled_start: Option<DateTime<Local>>,
+
+    // 试运行, 只生成订单不提交订单
+    #[serde(default)]
+    pub dry_run: bool,
+
+    // 监控模式, 只轮询库存并在有票时通知, 不下单
+    #[serde(default)]
+    pub monitor: bool,
+
+    // 保存请求参数及原始响应的目录, 由命令行--debug-dump设置
+    #[serde(skip)]
+    pub debug_dump: Option<PathBuf>,
+
+    // 触发滑块/验证码时暂停抢购的时间, 单位秒, 0为直接停止抢购
+    #[serde(default)]
+    pub captcha_pause_secs: u64,
+
+    // 会话过期时刷新会话并重新运行一次
+    #[serde(default)]
+    pub auto_relogin: bool,
+
+    // 最长运行时间, 单位秒, 从开始运行计时, 超出后停止等待/重试/捡漏
+    #[serde(default)]
+    pub max_runtime_seconds: Option<u64>,
+}
+
+// 提交订单的渠道参数
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OrderFeature {
+    // 子渠道
+    #[serde(default = "default_sub_channel")]
+    pub sub_channel: String,
+
+    // 支付成功后的跳转地址
+    #[serde(default = "default_return_url")]
+    pub return_url: String,
+
+    // 服务版本
+    #[serde(default = "default_service_version")]
+    pub service_version: String,
+
+    // 数据标签
+    #[serde(default = "default_data_tags")]
+    pub data_tags: String,
+}
+
+impl Default for OrderFeature {
+    fn default() -> Self {
+        Self {
+            sub_channel: default_sub_channel(),
+            return_url: default_return_url(),
+            service_version: default_service_version(),
+            data_tags: default_data_tags(),
+        }
+    }
+}
+
+fn default_sub_channel() -> String {
+    "damai@damaih5_h5".to_string()
+}
+
+fn default_return_url() -> String {
+    "https://m.damai.cn/damai/pay-success/index.html?spm=a2o71.orderconfirm.bottom.dconfirm&sqm=dianying.h5.unknown.value".to_string()
+}
+
+fn default_service_version() -> String {
+    "2.0.0".to_string()
+}
+
+fn default_data_tags() -> String {
+    "sqm:dianying.h5.unknown.value".to_string()
+}
+
+// 通知配置
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct NotifyConfig {
+    // Webhook地址, 抢购成功后POST JSON数据到该地址
+    #[serde(default)]
+    pub webhook: Option<String>,
+
+    // Telegram机器人配置
+    #[serde(default)]
+    pub telegram: Option<TelegramConfig>,
+
+    // Server酱SendKey
+    #[serde(default)]
+    pub push_key: Option<String>,
+
+    // Bark推送地址, 如: https://api.day.app/your_key
+    #[serde(default)]
+    pub bark_url: Option<String>,
+
+    // 邮件通知配置
+    #[serde(default)]
+    pub email: Option<EmailConfig>,
+
+    // 桌面通知, 需使用desktop-notify功能编译
+    #[serde(default)]
+    pub desktop: bool,
+
+    // 出现cookie过期等异常时是否通知
+    #[serde(default)]
+    pub notify_on_error: bool,
+}
+
+// 邮件通知配置
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EmailConfig {
+    // SMTP服务器地址
+    pub host: String,
+
+    // SMTP端口, 465使用SSL, 其余使用STARTTLS
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+
+    // 登录账号
+    pub username: String,
+
+    // 登录密码/授权码
+    pub password: String,
+
+    // 发件人, 默认与登录账号一致
+    #[serde(default)]
+    pub from: Option<String>,
+
+    // 收件人
+    pub to: String,
+}
+
+// SMTP默认端口
+fn default_smtp_port() -> u16 {
+    465
+}
+
+// Telegram机器人配置
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+// appKey及接口版本配置
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiConfig {
+    // 签名使用的appKey
+    #[serde(default = "default_app_key")]
+    pub app_key: String,
+
+    // 各接口版本号
+    #[serde(default)]
+    pub versions: ApiVersions,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            app_key: default_app_key(),
+            versions: ApiVersions::default(),
+        }
+    }
+}
+
+fn default_app_key() -> String {
+    DEFAULT_APP_KEY.to_string()
+}
+
+// 各接口版本号, 如: mtop.trade.order.build.h5/4.0/中的4.0
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ApiVersions {
+    // 用户信息
+    pub user_info: String,
+
+    // 门票信息
+    pub ticket_info: String,
+
+    // 场次/票档信息
+    pub perform_info: String,
+
+    // 生成订单
+    pub build_order: String,
+
+    // 提交订单
+    pub submit_order: String,
+}
+
+impl Default for ApiVersions {
+    fn default() -> Self {
+        Self {
+            user_info: DEFAULT_USER_INFO_VERSION.to_string(),
+            ticket_info: DEFAULT_TICKET_INFO_VERSION.to_string(),
+            perform_info: DEFAULT_PERFORM_INFO_VERSION.to_string(),
+            build_order: DEFAULT_BUILD_ORDER_VERSION.to_string(),
+            submit_order: DEFAULT_SUBMIT_ORDER_VERSION.to_string(),
+        }
+    }
+}
+
+// 开抢前最后阶段的等待方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SpinMode {
+    // 每1毫秒休眠后检查时间, 占用CPU少, 精度受系统定时器影响
+    #[default]
+    Sleep,
+
+    // 循环读取当前时间直到发包, 精度高, 期间占满一个CPU核心
+    Spin,
+}
+
+// 重试间隔策略
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryStrategy {
+    // 固定间隔: retry_interval
+    Fixed,
+
+    // 线性递增: retry_interval * 重试次数, 最多等待60秒
+    Linear,
+
+    // 指数递增: retry_interval * 2^(重试次数-1), 最多等待60秒
+    Exponential,
+
+    // 旧版策略: 偶数次等待100毫秒, 奇数次按每5.5秒一轮扣除已耗时间, 不读取retry_interval
+    #[default]
+    Legacy,
+}
+
+// 线性/指数递增策略的最大等待时间, 单位毫秒
+const MAX_RETRY_INTERVAL: u64 = 60 * 1000;
+
+// 旧版策略偶数次的重试间隔, 单位毫秒
+const LEGACY_RETRY_INTERVAL: u64 = 100;
+
+impl RetryStrategy {
+    // 计算重试等待时间, attempt从0开始, run_time为已耗时间, min_time为单次最小耗时, 单位毫秒
+    pub fn interval(&self, attempt: u8, retry_interval: u64, run_time: u64, min_time: u64) -> u64 {
+        let times = attempt as u64 + 1;
+        match self {
+            Self::Fixed => retry_interval,
+            Self::Linear => retry_interval.saturating_mul(times).min(MAX_RETRY_INTERVAL),
+            Self::Exponential => retry_interval
+                .saturating_mul(1 << (times - 1).min(16))
+                .min(MAX_RETRY_INTERVAL),
+            Self::Legacy => match attempt % 2 == 0 {
+                true => LEGACY_RETRY_INTERVAL,
+                false => ((times / 2) * 5500)
+                    .saturating_sub(run_time)
+                    .saturating_sub(min_time)
+                    .max(LEGACY_RETRY_INTERVAL),
+            },
+        }
+    }
+}
+
+impl Ticket {
+    // 按优先级排列的场次序号
+    pub fn session_list(&self) -> Vec<usize> {
+        match self.session_priority.is_empty() {
+            true => vec![self.sessions],
+            false => self.session_priority.clone(),
+        }
+    }
+
+    // 检查实名观演人配置, all_real_names与real_names互斥
+    pub fn check_real_names(&self) -> Result<()> {
+        match self.all_real_names && !self.real_names.is_empty() {
+            true => Err(anyhow!(
+                "ticket.all_real_names与ticket.real_names不能同时配置"
+            )),
+            false => Ok(()),
+        }
+    }
+
+    // 按优先级排列的票档序号
+    pub fn grade_list(&self) -> Vec<usize> {
+        match self.grade_priority.is_empty() {
+            true => vec![self.grade],
+            false => self.grade_priority.clone(),
+        }
+    }
+}
+
+// 解析门票ID, 支持纯数字ID或大麦分享链接, 如: https://m.damai.cn/damai/detail/item.html?itemId=123456
+pub fn parse_ticket_id(input: &str) -> Result<String> {
+    let input = input.trim();
+    if !input.is_empty() && input.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(input.to_string());
+    }
+    let id_regex = Regex::new(r"[?&#](?:itemId|id)=(\d+)")?;
+    id_regex
+        .captures(input)
+        .and_then(|capture| capture.get(1))
+        .map(|id| id.as_str().to_string())
+        .ok_or_else(|| anyhow!("无法从:{}中解析门票ID, 请填写门票ID或大麦门票链接", input))
+}
+
+impl Account {
+    // 获取cookie, 优先从配置读取, 否则从cookie文件读取
+    pub fn load_cookie(&self) -> Result<String> {
+        match (&self.cookie_file, self.cookie.trim().is_empty()) {
+            (Some(_), false) => Err(anyhow!(
+                "账号:{}, cookie与cookie_file不能同时配置",
+                self.remark
+            )),
+            (Some(path), true) => Ok(std::fs::read_to_string(path)
+                .map_err(|e| anyhow!("读取cookie文件:{:?}失败, {}", path, e))?
+                .trim()
+                .to_string()),
+            (None, false) => Ok(self.cookie.clone()),
+            (None, true) => Err(anyhow!("账号:{}, 未配置cookie", self.remark)),
+        }
+    }
+
+    // 请求使用的User-Agent, 获取token与请求API使用同一个
+    pub fn user_agent(&self) -> String {
+        match &self.user_agent {
+            Some(user_agent) if !user_agent.trim().is_empty() => user_agent.trim().to_string(),
+            _ => pick_user_agent(&self.remark).to_string(),
+        }
+    }
+
+    // 当前账号的cookie存储文件, 文件名为账号备注, 避免多个账号共用cookie
+    pub fn cookie_store_path(&self) -> Option<PathBuf> {
+        let file_name = self
+            .remark
+            .replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_");
+        self.cookie_store_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", file_name)))
+    }
+
+    // 同时进行中的生成订单请求数上限, 未配置时与parallel_builds一致, 避免并发流程被串行化
+    pub fn max_inflight(&self) -> usize {
+        self.max_inflight.unwrap_or(self.parallel_builds).max(1)
+    }
+
+    // 生成订单后等待时间的范围(下限, 上限), 单位毫秒
+    pub fn wait_for_submit_range(&self) -> (u64, u64) {
+        (
+            self.wait_for_submit_time_min.unwrap_or(self.wait_for_submit_time),
+            self.wait_for_submit_time_max.unwrap_or(self.wait_for_submit_time),
+        )
+    }
+
+    // 从环境变量读取敏感配置, 支持${ENV_VAR}占位符, 值为空时读取默认环境变量:
+    // cookie: DM_COOKIE, notify.telegram.bot_token: DM_TELEGRAM_BOT_TOKEN,
+    // notify.email.password: DM_SMTP_PASSWORD, proxy: DM_PROXY
+    // cookie/proxy属于单个账号, 仅account_env为true(只配置了一个账号)时读取默认环境变量, 多账号需使用占位符
+    pub fn resolve_secrets(&mut self, account_env: bool) -> Result<()> {
+        self.resolve_secrets_with(account_env, &|name| std::env::var(name).ok())
+    }
+
+    // 同resolve_secrets, env为环境变量读取函数, 测试时可替换, 避免修改进程的环境变量
+    fn resolve_secrets_with(
+        &mut self,
+        account_env: bool,
+        env: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<()> {
+        let prefix = |field: &str| format!("账号:{}, {}", self.remark, field);
+        if self.cookie_file.is_none() {
+            self.cookie = match account_env {
+                true => resolve_secret(&self.cookie, "DM_COOKIE", &prefix("cookie"), env)?,
+                false => expand_env(&self.cookie, &prefix("cookie"), env)?,
+            };
+        }
+        if let Some(telegram) = self.notify.telegram.as_mut() {
+            telegram.bot_token = resolve_secret(
+                &telegram.bot_token,
+                "DM_TELEGRAM_BOT_TOKEN",
+                &prefix("notify.telegram.bot_token"),
+                env,
+            )?;
+        }
+        if let Some(email) = self.notify.email.as_mut() {
+            email.password = resolve_secret(
+                &email.password,
+                "DM_SMTP_PASSWORD",
+                &prefix("notify.email.password"),
+                env,
+            )?;
+        }
+        self.proxy = match self.proxy.as_deref() {
+            Some(proxy) => Some(expand_env(proxy, &prefix("proxy"), env)?),
+            None if account_env => env("DM_PROXY").filter(|proxy| !proxy.trim().is_empty()),
+            None => None,
+        };
+        Ok(())
+    }
+
+    // 校验配置, 列出所有错误
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+        let ticket = &self.ticket;
+
+        match self.load_cookie() {
+            Ok(cookie) if cookie.trim().is_empty() => errors.push("cookie为空".to_string()),
+            Ok(_) => {}
+            Err(e) => errors.push(e.to_string()),
+        }
+        if let Err(e) = parse_ticket_id(&ticket.id) {
+            errors.push(e.to_string());
+        }
+        if ticket.num == 0 {
+            errors.push("ticket.num 必须大于等于1".to_string());
+        }
+        if ticket.session_date.is_none() {
+            if ticket.sessions == 0 {
+                errors.push("ticket.sessions 必须大于等于1".to_string());
+            }
+            if ticket.session_priority.contains(&0) {
+                errors.push("ticket.session_priority 的序号必须大于等于1".to_string());
+            }
+        }
+        match ticket.max_price {
+            Some(max_price) if max_price <= 0.0 => {
+                errors.push("ticket.max_price 必须大于0".to_string())
+            }
+            Some(_) => {}
+            None => {
+                if ticket.grade == 0 {
+                    errors.push("ticket.grade 必须大于等于1".to_string());
+                }
+                if ticket.grade_priority.contains(&0) {
+                    errors.push("ticket.grade_priority 的序号必须大于等于1".to_string());
+                }
+            }
+        }
+        if ticket.real_names.contains(&0) {
+            errors.push("ticket.real_names 的序号必须大于等于1".to_string());
+        }
+        if let Err(e) = ticket.check_real_names() {
+            errors.push(e.to_string());
+        }
+        if ticket.split_across_grades && ticket.all_real_names {
+            errors.push("ticket.split_across_grades与ticket.all_real_names不能同时配置".to_string());
+        }
+        if let Some(viewer_count) = ticket.viewer_count {
+            if viewer_count == 0 {
+                errors.push("ticket.viewer_count 必须大于等于1".to_string());
+            }
+            if ticket.all_real_names {
+                errors.push("ticket.viewer_count不能与ticket.all_real_names同时配置".to_string());
+            }
+            // 配置了real_names时选择其中前viewer_count位
+            if !ticket.real_names.is_empty() && viewer_count > ticket.real_names.len() {
+                errors.push(format!(
+                    "ticket.viewer_count:{} 不能大于ticket.real_names的数量:{}",
+                    viewer_count,
+                    ticket.real_names.len()
+                ));
+            }
+            if ticket.split_across_grades {
+                errors.push("ticket.viewer_count不能与ticket.split_across_grades同时配置".to_string());
+            }
+        }
+        if ticket.priority_purchase_time < 0 {
+            errors.push("ticket.priority_purchase_time 不能为负数".to_string());
+        }
+        if ticket.pick_up_leaks.grades.contains(&0) {
+            errors.push("ticket.pick_up_leaks.grades 的序号必须大于等于1".to_string());
+        }
+        if ticket.pick_up_leaks.grace_period_minutes < 0 {
+            errors.push("ticket.pick_up_leaks.grace_period_minutes 不能为负数".to_string());
+        }
+
+        if self.interval == 0 {
+            errors.push("interval 必须大于0".to_string());
+        }
+        if self.early_submit_time < 0 {
+            errors.push("early_submit_time 不能为负数".to_string());
+        }
+        if self.retry_times == 0 {
+            errors.push("retry_times 必须大于等于1".to_string());
+        }
+        if let Some(local_address) = self.local_address {
+            // 绑定随机端口检查该地址是否属于本机网卡
+            if let Err(e) = UdpSocket::bind(SocketAddr::new(local_address, 0)) {
+                errors.push(format!("local_address:{} 不是本机地址, {}", local_address, e));
+            }
+        }
+        if self.request_timeout_ms == 0 {
+            errors.push("request_timeout_ms 必须大于0".to_string());
+        }
+        if self.parallel_builds == 0 {
+            errors.push("parallel_builds 必须大于等于1".to_string());
+        }
+        if self.max_inflight == Some(0) {
+            errors.push("max_inflight 必须大于等于1".to_string());
+        }
+        let (wait_min, wait_max) = self.wait_for_submit_range();
+        if wait_min > wait_max {
+            errors.push(format!(
+                "wait_for_submit_time_min({}) 不能大于 wait_for_submit_time_max({})",
+                wait_min, wait_max
+            ));
+        }
+
+        let notify = &self.notify;
+        let is_blank = |value: &Option<String>| matches!(value, Some(v) if v.trim().is_empty());
+        if is_blank(&notify.webhook) {
+            errors.push("notify.webhook 不能为空".to_string());
+        }
+        if is_blank(&notify.push_key) {
+            errors.push("notify.push_key 不能为空".to_string());
+        }
+        if is_blank(&notify.bark_url) {
+            errors.push("notify.bark_url 不能为空".to_string());
+        }
+        if let Some(telegram) = &notify.telegram {
+            if telegram.bot_token.trim().is_empty() || telegram.chat_id.trim().is_empty() {
+                errors.push("notify.telegram 的bot_token/chat_id不能为空".to_string());
+            }
+        }
+        if let Some(email) = &notify.email {
+            if email.host.trim().is_empty()
+                || email.username.trim().is_empty()
+                || email.to.trim().is_empty()
+            {
+                errors.push("notify.email 的host/username/to不能为空".to_string());
+            }
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(anyhow!(
+                "账号:{}, 配置错误:\n{}",
+                self.remark,
+                errors
+                    .iter()
+                    .enumerate()
+                    .map(|(i, e)| format!("\t{}. {}", i + 1, e))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )),
+        }
+    }
 }
 
 // 捡漏配置
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PickUpLeaks {
+    // 是否开启捡漏, 关闭后未抢到票直接退出
+    #[serde(default = "default_pick_up_leaks_enabled")]
+    pub enabled: bool,
+
     // 轮询间隔
     #[serde(default = "default_pick_up_leaks_interval")]
     pub interval: u64,
@@ -84,7 +832,7 @@ pub struct PickUpLeaks {
     #[serde(default = "default_pick_up_leaks_times")]
     pub times: u64,
 
-    // 票挡序号
+    // 票挡序号, 按优先级排列, 多个票档同时有票时优先购买靠前的票档
     #[serde(default = "default_pick_up_leaks_grades")]
     pub grades: Vec<usize>,
 
@@ -95,6 +843,24 @@ pub struct PickUpLeaks {
     // 进入捡漏模式的宽限期
     #[serde(default = "default_grace_period_minutes")]
     pub grace_period_minutes: i64,
+
+    // 轮询间隔随机抖动, 每次等待interval ± 0~jitter_ms毫秒, 默认0不抖动
+    #[serde(default)]
+    pub jitter_ms: u64,
+}
+
+impl Default for PickUpLeaks {
+    fn default() -> Self {
+        Self {
+            enabled: default_pick_up_leaks_enabled(),
+            interval: default_pick_up_leaks_interval(),
+            times: default_pick_up_leaks_times(),
+            grades: default_pick_up_leaks_grades(),
+            num: default_pick_up_leaks_num(),
+            grace_period_minutes: default_grace_period_minutes(),
+            jitter_ms: 0,
+        }
+    }
 }
 
 // 进入捡漏模式的宽限期
@@ -120,11 +886,30 @@ fn default_pick_up_leaks_grades() -> Vec<usize> {
 fn default_pick_up_leaks_num() -> usize {
     0
 }
+
+// 默认开启捡漏
+fn default_pick_up_leaks_enabled() -> bool {
+    true
+}
 // 轮询开抢时间间隔的默认值, 单位毫秒
 fn default_interval() -> u64 {
     30
 }
 
+// 切换为精确等待的时间
+fn default_precise_wait_threshold() -> i64 {
+    1000
+}
+
+fn default_final_spin_ms() -> i64 {
+    200
+}
+
+// 开抢前预热连接的时间
+fn default_warmup_lead_time() -> i64 {
+    3000
+}
+
 // 提早提交数据的时间
 fn default_early_submit_time() -> i64 {
     0
@@ -145,6 +930,70 @@ fn default_retry_interval() -> u64 {
     100
 }
 
+// 解析本地时间, 支持: 2024-06-15 11:50:00 及 RFC3339格式
+fn deserialize_local_datetime<'de, D>(deserializer: D) -> Result<Option<DateTime<Local>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = match Option::<String>::deserialize(deserializer)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(&value) {
+        return Ok(Some(datetime.with_timezone(&Local)));
+    }
+    NaiveDateTime::parse_from_str(&value, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .and_then(|datetime| Local.from_local_datetime(&datetime).single())
+        .map(Some)
+        .ok_or_else(|| {
+            de::Error::custom(format!(
+                "时间格式错误:{}, 示例: 2024-06-15 11:50:00",
+                value
+            ))
+        })
+}
+
+// 下单记录文件
+fn default_order_record_file() -> PathBuf {
+    PathBuf::from("./orders.jsonl")
+}
+
+// API域名
+fn default_base_domain() -> String {
+    DEFAULT_BASE_DOMAIN.to_string()
+}
+
+// 单个请求超时时间
+fn default_request_timeout_ms() -> u64 {
+    5000
+}
+
+// 门票/场次信息缓存时间
+fn default_info_cache_ttl_secs() -> u64 {
+    60
+}
+
+// 并发抢购数
+fn default_parallel_builds() -> usize {
+    1
+}
+
+// 并发抢购错开时间
+fn default_parallel_stagger() -> u64 {
+    20
+}
+
+// 使用同一订单重试提交的次数
+fn default_submit_retry_times() -> u8 {
+    1
+}
+
+// 请求超时/连接失败时的重试次数
+fn default_transport_retry_times() -> u8 {
+    2
+}
+
 // 抢购失败的重试间隔
 fn default_wait_for_submit_time() -> u64 {
     30
@@ -153,6 +1002,62 @@ fn default_wait_for_submit_time() -> u64 {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
     pub accounts: Vec<Account>,
+
+    // 所有账号每秒最多发送的请求数, 默认0, 不限速
+    #[serde(default)]
+    pub max_requests_per_second: u32,
+
+    // 任一账号抢购成功后停止其他账号, 用于多个账号抢同一张票
+    #[serde(default)]
+    pub stop_on_success: bool,
+
+    // 输出语言, zh: 中文, en: 英文
+    #[serde(default)]
+    pub lang: Lang,
+
+    // 日志级别, error/warn/info/debug/trace, 默认info。命令行-v/-q及RUST_LOG环境变量优先
+    #[serde(default)]
+    pub log_level: Option<LogLevel>,
+}
+
+impl Config {
+    // 按账号备注筛选账号, 未指定时保留全部账号
+    pub fn select_account(&mut self, remark: Option<&str>) -> Result<()> {
+        if let Some(remark) = remark {
+            self.accounts.retain(|account| account.remark == remark);
+            if self.accounts.is_empty() {
+                return Err(anyhow!("未找到账号备注为:{}的账号", remark));
+            }
+        }
+        Ok(())
+    }
+
+    // 将配置了多个门票的账号拆分为每个门票一个账号, 备注为: 账号备注#序号
+    pub fn expand_tickets(&mut self) -> Result<()> {
+        let mut accounts = Vec::new();
+        for mut account in self.accounts.drain(..) {
+            account.group = account.remark.clone();
+            if account.tickets.is_empty() {
+                accounts.push(account);
+                continue;
+            }
+            if !account.ticket.id.trim().is_empty() {
+                return Err(anyhow!(
+                    "账号:{}, ticket与tickets不能同时配置",
+                    account.remark
+                ));
+            }
+            let tickets = std::mem::take(&mut account.tickets);
+            for (i, ticket) in tickets.into_iter().enumerate() {
+                let mut target = account.clone();
+                target.remark = format!("{}#{}", account.remark, i + 1);
+                target.ticket = ticket;
+                accounts.push(target);
+            }
+        }
+        self.accounts = accounts;
+        Ok(())
+    }
 }
 
 // 加载位置文件
@@ -184,8 +1089,178 @@ where
 
 
 
-pub fn load_global_config() -> Option<Config> {
-    let args: Vec<String> = env::args().collect();
-    let config_path = format!("./config/{}", &args[1]);
-    load_config(&config_path)
+// 加载配置文件, 文件不存在时到./config目录下查找, 兼容: dm-ticket config.yaml
+pub fn load_global_config(path: &Path) -> Option<Config> {
+    let config_path = match path.exists() {
+        true => path.to_path_buf(),
+        false => Path::new("./config").join(path),
+    };
+    let mut config: Config = load_config(&config_path.to_string_lossy())?;
+    let account_env = config.accounts.len() == 1;
+    for account in config.accounts.iter_mut() {
+        if let Err(e) = account.resolve_secrets(account_env) {
+            error!("{}", e);
+            return None;
+        }
+    }
+    Some(config)
+}
+
+// 替换配置值中的${ENV_VAR}占位符为环境变量的值, 环境变量不存在时返回错误
+fn expand_env(value: &str, field: &str, env: &dyn Fn(&str) -> Option<String>) -> Result<String> {
+    let regex = Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}")?;
+    let mut missing = Vec::new();
+    let expanded = regex.replace_all(value, |captures: &regex::Captures| {
+        env(&captures[1]).unwrap_or_else(|| {
+            missing.push(captures[1].to_string());
+            String::new()
+        })
+    });
+    match missing.is_empty() {
+        true => Ok(expanded.into_owned()),
+        false => Err(anyhow!("{} 引用的环境变量:{}未设置", field, missing.join(", "))),
+    }
+}
+
+// 敏感配置: 值为空时读取默认环境变量, 否则替换${ENV_VAR}占位符
+fn resolve_secret(
+    value: &str,
+    default_env: &str,
+    field: &str,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> Result<String> {
+    match value.trim().is_empty() {
+        true => Ok(env(default_env).unwrap_or_default()),
+        false => expand_env(value, field, env),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_inflight_defaults_to_parallel_builds() {
+        let mut account = crate::testing::account();
+        account.parallel_builds = 3;
+        assert_eq!(account.max_inflight(), 3);
+
+        account.max_inflight = Some(1);
+        assert_eq!(account.max_inflight(), 1);
+    }
+
+    #[test]
+    fn account_env_only_for_single_account() {
+        let env = |name: &str| (name == "DM_COOKIE").then(|| "cookie2=env".to_string());
+        let mut account = crate::testing::account();
+        account.cookie = String::new();
+
+        // 多账号时不读取DM_COOKIE, 避免所有账号使用同一cookie
+        account.resolve_secrets_with(false, &env).unwrap();
+        assert_eq!(account.cookie, "");
+
+        account.resolve_secrets_with(true, &env).unwrap();
+        assert_eq!(account.cookie, "cookie2=env");
+    }
+
+    #[test]
+    fn env_placeholders_expanded() {
+        let env = |name: &str| (name == "MY_PROXY").then(|| "http://127.0.0.1:7890".to_string());
+        let mut account = crate::testing::account();
+        account.proxy = Some("${MY_PROXY}".to_string());
+        account.resolve_secrets_with(false, &env).unwrap();
+        assert_eq!(account.proxy.as_deref(), Some("http://127.0.0.1:7890"));
+
+        // 引用的环境变量不存在时返回错误
+        account.proxy = Some("${MISSING_PROXY}".to_string());
+        assert!(account.resolve_secrets_with(false, &env).is_err());
+    }
+
+    #[test]
+    fn legacy_retry_interval_no_underflow() {
+        let strategy = RetryStrategy::Legacy;
+        // 已耗时间超过本轮时长时不会溢出, 使用最小间隔
+        assert_eq!(strategy.interval(1, 0, u64::MAX, u64::MAX), LEGACY_RETRY_INTERVAL);
+        assert_eq!(strategy.interval(3, 0, 100_000, 9999), LEGACY_RETRY_INTERVAL);
+        assert_eq!(strategy.interval(u8::MAX, 0, u64::MAX, 0), LEGACY_RETRY_INTERVAL);
+        // 偶数次固定间隔
+        assert_eq!(strategy.interval(0, 0, u64::MAX, 0), LEGACY_RETRY_INTERVAL);
+        // 奇数次扣除已耗时间
+        assert_eq!(strategy.interval(1, 0, 1000, 500), 4000);
+    }
+
+    #[test]
+    fn exponential_retry_interval_capped() {
+        let strategy = RetryStrategy::Exponential;
+        assert_eq!(strategy.interval(0, 100, 0, 0), 100);
+        assert_eq!(strategy.interval(2, 100, 0, 0), 400);
+        assert_eq!(strategy.interval(u8::MAX, u64::MAX, 0, 0), MAX_RETRY_INTERVAL);
+    }
+
+    #[test]
+    fn linear_retry_interval_capped() {
+        let strategy = RetryStrategy::Linear;
+        assert_eq!(strategy.interval(0, 100, 0, 0), 100);
+        assert_eq!(strategy.interval(2, 100, 0, 0), 300);
+        assert_eq!(strategy.interval(u8::MAX, 1000, 0, 0), MAX_RETRY_INTERVAL);
+        // 溢出时不panic
+        assert_eq!(strategy.interval(u8::MAX, u64::MAX, 0, 0), MAX_RETRY_INTERVAL);
+    }
+
+    #[test]
+    fn parse_ticket_id_from_id_or_url() {
+        assert_eq!(parse_ticket_id(" 123456 ").unwrap(), "123456");
+        assert_eq!(
+            parse_ticket_id("https://m.damai.cn/item.html?itemId=123456&spm=a").unwrap(),
+            "123456"
+        );
+        assert_eq!(
+            parse_ticket_id("https://detail.damai.cn/item.htm?spm=a2oeg&id=654321").unwrap(),
+            "654321"
+        );
+        assert!(parse_ticket_id("").is_err());
+        assert!(parse_ticket_id("https://m.damai.cn/damai/detail/item.html").is_err());
+    }
+
+    #[test]
+    fn user_agent_configured_or_stable_per_account() {
+        let mut account = crate::testing::account();
+        account.user_agent = Some(" Mozilla/5.0 test ".to_string());
+        assert_eq!(account.user_agent(), "Mozilla/5.0 test");
+
+        // 未配置时按账号备注选择, 多次获取结果一致
+        account.user_agent = None;
+        assert_eq!(account.user_agent(), pick_user_agent(&account.remark));
+        assert_eq!(account.user_agent(), account.user_agent());
+
+        account.user_agent = Some("  ".to_string());
+        assert_eq!(account.user_agent(), pick_user_agent(&account.remark));
+    }
+
+    #[test]
+    fn local_address_must_be_local() {
+        let local_address_error = |account: &Account| {
+            account
+                .validate()
+                .err()
+                .map(|e| e.to_string().contains("local_address"))
+                .unwrap_or(false)
+        };
+        let mut account = crate::testing::account();
+        account.local_address = Some("127.0.0.1".parse().unwrap());
+        assert!(!local_address_error(&account));
+
+        // 203.0.113.0/24为文档保留地址, 不属于本机网卡
+        account.local_address = Some("203.0.113.1".parse().unwrap());
+        assert!(local_address_error(&account));
+
+        // 非IP地址在解析配置时报错
+        let result = serde_json::from_value::<Account>(serde_json::json!({
+            "remark": "测试账号",
+            "cookie": "cookie2=test",
+            "ticket": {"id": "123456", "num": 1, "sessions": 1, "grade": 1},
+            "local_address": "not-an-ip",
+        }));
+        assert!(result.is_err());
+    }
 }
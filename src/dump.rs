@@ -0,0 +1,93 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// 文件序号, 同一毫秒内多个请求时避免文件名重复
+static DUMP_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+// 从请求地址中解析API名称, 如: https://mtop.damai.cn/h5/mtop.trade.order.build.h5/4.0/ => mtop.trade.order.build.h5
+pub fn api_name(url: &str) -> &str {
+    url.split('/')
+        .find(|part| part.starts_with("mtop."))
+        .unwrap_or("response")
+}
+
+// 请求参数及原始响应, 用于排查接口返回异常
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResponseDump {
+    // API名称
+    pub api: String,
+
+    // 请求地址
+    pub url: String,
+
+    // URL参数
+    pub params: Value,
+
+    // 表单数据
+    pub form: Value,
+
+    // HTTP状态码
+    pub status: u16,
+
+    // 原始响应内容
+    pub body: String,
+
+    // 请求时间
+    pub time: String,
+}
+
+impl ResponseDump {
+    pub fn new(url: &str, params: &Value, form: &Value, status: u16, body: &str) -> Self {
+        let local: DateTime<Local> = Local::now();
+        Self {
+            api: api_name(url).to_string(),
+            url: url.to_string(),
+            params: params.clone(),
+            form: form.clone(),
+            status,
+            body: body.to_string(),
+            time: local.format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+        }
+    }
+
+    // 写入目录, 文件名为: 时间-序号-API名称.json, 返回文件路径
+    pub fn write_to(&self, dir: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(dir).map_err(|e| anyhow!("创建目录:{:?}失败, {}", dir, e))?;
+        let local: DateTime<Local> = Local::now();
+        let seq = DUMP_SEQ.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!(
+            "{}-{:04}-{}.json",
+            local.format("%Y%m%d-%H%M%S%.3f"),
+            seq,
+            self.api
+        ));
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+
+    // 从文件读取
+    pub fn read_from(path: &Path) -> Result<Self> {
+        let content =
+            fs::read_to_string(path).map_err(|e| anyhow!("读取文件:{:?}失败, {}", path, e))?;
+        serde_json::from_str(&content).map_err(|e| anyhow!("解析文件:{:?}失败, {}", path, e))
+    }
+
+    // 读取目录下的所有.json文件, 按文件名(即保存时间)排序
+    pub fn load_dir(dir: &Path) -> Result<Vec<Self>> {
+        let mut paths = fs::read_dir(dir)
+            .map_err(|e| anyhow!("读取目录:{:?}失败, {}", dir, e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.iter().map(|path| Self::read_from(path)).collect()
+    }
+}
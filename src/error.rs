@@ -1,11 +1,160 @@
+use serde_json::Value;
 use thiserror::Error;
 
+// 滑块/验证码验证的关键字
+const CAPTCHA_KEYWORDS: [&str; 5] = ["captcha", "punish", "x5sec", "滑块", "验证码"];
+
 // Api返回的错误信息
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum DmApiError {
     #[error("B-00203-200-034::您选购的商品信息已过期，请重新查询")]
-    ProductEpired,
+    ProductExpired,
 
     #[error("RGV587_ERROR::SM::哎哟喂,被挤爆啦,请稍后重试")]
     SystemBusy,
+
+    #[error("FAIL_SYS_SESSION_EXPIRED::Session过期")]
+    SessionExpired,
+
+    #[error("超出购买数量限制")]
+    PurchaseLimit,
+
+    // 库存不足购买数量, 减少数量后可能购买成功
+    #[error("库存不足")]
+    StockShortage,
+
+    #[error("FAIL_SYS_USER_VALIDATE::触发风控验证")]
+    RiskControl,
+
+    // 需要完成滑块/验证码验证, 附带验证地址
+    #[error("触发滑块/验证码验证, 请完成验证后重试")]
+    CaptchaRequired(Option<String>),
+
+    // 已有该演出的订单, 附带返回信息中的订单号
+    #[error("已有该演出的订单, 请勿重复下单")]
+    AlreadyOrdered(Option<String>),
+
+    #[error("{0}")]
+    Unknown(String),
+}
+
+#[allow(non_upper_case_globals)]
+impl DmApiError {
+    #[deprecated(note = "请使用DmApiError::ProductExpired")]
+    pub const ProductEpired: DmApiError = DmApiError::ProductExpired;
+
+    // 根据返回的ret解析错误类型, 如: B-00203-200-034::您选购的商品信息已过期，请重新查询
+    pub fn from_ret(ret: &[String]) -> Self {
+        let ret = match ret.first() {
+            Some(ret) => ret,
+            None => return Self::Unknown("未知错误".to_string()),
+        };
+        if is_captcha(ret) {
+            return Self::CaptchaRequired(None);
+        }
+        let code = ret.split("::").next().unwrap_or_default();
+        match code {
+            "B-00203-200-034" => Self::ProductExpired,
+            "RGV587_ERROR" => Self::SystemBusy,
+            "FAIL_SYS_SESSION_EXPIRED" => Self::SessionExpired,
+            "FAIL_SYS_USER_VALIDATE" | "FAIL_SYS_ILLEGAL_ACCESS" => Self::RiskControl,
+            _ if is_already_ordered(ret) => Self::AlreadyOrdered(find_order_ref(ret)),
+            // 库存不足的提示可能包含"购买数量", 需先于限购判断
+            _ if ret.contains("库存不足") || ret.contains("余票不足") => Self::StockShortage,
+            _ if ["限购", "超出购买数量", "超过购买数量"]
+                .iter()
+                .any(|keyword| ret.contains(keyword)) =>
+            {
+                Self::PurchaseLimit
+            }
+            _ => Self::Unknown(ret.clone()),
+        }
+    }
+
+    // 根据返回的ret及data解析错误类型, 无法识别的错误在data中包含验证地址时为CaptchaRequired
+    // 系统繁忙等已识别的错误也可能附带punish/x5sec地址, 保持原错误类型以便按原逻辑处理(如刷新会话)
+    pub fn from_res(ret: &[String], data: &Value) -> Self {
+        let url = data["url"].as_str().filter(|url| is_captcha(url));
+        match (Self::from_ret(ret), url) {
+            (Self::Unknown(_) | Self::CaptchaRequired(None), Some(url)) => {
+                Self::CaptchaRequired(Some(url.to_string()))
+            }
+            (error, _) => error,
+        }
+    }
+}
+
+// 是否为滑块/验证码验证
+fn is_captcha(text: &str) -> bool {
+    let text = text.to_lowercase();
+    CAPTCHA_KEYWORDS.iter().any(|keyword| text.contains(keyword))
+}
+
+// 是否为已有订单的错误, 如: 您已有未支付订单, 请勿重复下单
+fn is_already_ordered(ret: &str) -> bool {
+    ["未支付订单", "已有订单", "订单已存在", "重复下单"]
+        .iter()
+        .any(|keyword| ret.contains(keyword))
+}
+
+// 从返回信息中提取订单号(连续10位以上数字)
+fn find_order_ref(ret: &str) -> Option<String> {
+    ret.split(|c: char| !c.is_ascii_digit())
+        .find(|part| part.len() >= 10)
+        .map(|part| part.to_string())
+}
+
+// 程序运行中的错误
+#[derive(Error, Debug)]
+pub enum DmTicketError {
+    #[error("CTRL-C, 退出程序...")]
+    Cancelled,
+
+    // 提交订单的请求已发出但未收到有效响应(超时/网络错误等), 订单可能已创建, 不能重新提交
+    #[error("提交订单结果未知({0}), 订单可能已创建, 为避免重复下单已停止抢购, 请前往手机APP查看订单")]
+    SubmitUncertain(String),
+
+    // 配置的实名观演人与账号的实名观演人不符, 重试无意义
+    #[error("{0}")]
+    InvalidViewers(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn system_busy_with_punish_url() {
+        let ret = vec!["RGV587_ERROR::SM::哎哟喂,被挤爆啦,请稍后重试".to_string()];
+        let data = json!({
+            "url": "https://mtop.damai.cn/h5/mtop.trade.order.build.h5/4.0/_____tmd_____/punish?x5secdata=xd5a2f1e9c&x5step=1",
+            "dialogSize": {"width": 400, "height": 300},
+            "action": "captcha",
+        });
+
+        // 系统繁忙需刷新会话, 不能被识别为滑块验证
+        assert_eq!(DmApiError::from_res(&ret, &data), DmApiError::SystemBusy);
+    }
+
+    #[test]
+    fn stock_shortage_before_purchase_limit() {
+        let from_ret = |ret: &str| DmApiError::from_ret(&[ret.to_string()]);
+
+        assert_eq!(from_ret("FAIL_BIZ::库存不足购买数量"), DmApiError::StockShortage);
+        assert_eq!(from_ret("FAIL_BIZ::余票不足购买数量"), DmApiError::StockShortage);
+        assert_eq!(from_ret("FAIL_BIZ::超出购买数量"), DmApiError::PurchaseLimit);
+        assert_eq!(from_ret("FAIL_BIZ::该商品限购2张"), DmApiError::PurchaseLimit);
+    }
+
+    #[test]
+    fn unknown_error_with_captcha_url() {
+        let ret = vec!["FAIL_SYS_UNKNOWN::未知错误".to_string()];
+        let url = "https://h5api.m.taobao.com/_____tmd_____/punish?x5secdata=abc";
+
+        assert_eq!(
+            DmApiError::from_res(&ret, &json!({ "url": url })),
+            DmApiError::CaptchaRequired(Some(url.to_string()))
+        );
+    }
 }
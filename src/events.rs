@@ -0,0 +1,175 @@
+use std::{
+    fmt::Debug,
+    io::{self, Write},
+    sync::Mutex,
+    time::Duration,
+};
+
+use fast_qr::QRBuilder;
+use log::{debug, info, warn};
+
+use crate::{
+    i18n::{tr, Msg},
+    models::perform::SaleState,
+    ticket::PurchasedTarget,
+};
+
+// 抢购过程中的状态事件, 用于命令行/TUI/GUI等展示, 与抢购逻辑分离
+// 多账号运行时可通过logger::ACCOUNT_REMARK获取当前账号备注, 所有方法默认不处理
+pub trait EventSink: Send + Sync + Debug {
+    // 等待开抢倒计时, 剩余毫秒数
+    fn on_countdown(&self, _remaining_ms: i64) {}
+
+    // 第attempt次(从0开始)生成/提交订单结束, latency为本次耗时, success为是否提交成功
+    fn on_attempt(&self, _attempt: u8, _latency: Duration, _success: bool) {}
+
+    // 监控模式第round次查询库存
+    fn on_stock_check(&self, _round: u64) {}
+
+    // 捡漏第round次查询库存, state为None时查询失败, found为是否有配置的票档可购买
+    fn on_leak_check(&self, _round: u64, _state: Option<SaleState>, _found: bool) {}
+
+    // 下单成功后的付款链接
+    fn on_payment_link(&self, _link: &str) {}
+
+    // 抢购成功, target为实际抢到的场次/票档
+    fn on_success(&self, _target: &PurchasedTarget) {}
+
+    // 抢购过程中的状态变化, 如等待完成验证
+    fn on_status(&self, _status: &str) {}
+
+    // 抢购过程中的异常, 如触发验证码(含验证地址)、cookie过期
+    fn on_error(&self, _error: &str) {}
+}
+
+// 毫秒转天时分秒
+fn split_millis(ms: i64) -> (i64, i64, i64, f64) {
+    let sec = ms as f64 / 1000.0;
+    let hours = (sec / 3600.0) as i64;
+    let rem = sec % 3600.0;
+    ((hours / 24), hours % 24, (rem / 60.0) as i64, rem % 60.0)
+}
+
+// 倒计时文本
+pub fn countdown_text(remaining_ms: i64) -> String {
+    let (days, hours, minutes, seconds) = split_millis(remaining_ms);
+    let seconds = format!("{:.3}", seconds);
+    match days > 0 {
+        true => tr(Msg::CountdownDays, &[&days, &hours, &minutes, &seconds]),
+        false => tr(Msg::Countdown, &[&hours, &minutes, &seconds]),
+    }
+}
+
+// 捡漏查询结果文本
+pub fn leak_check_text(state: Option<SaleState>, found: bool) -> String {
+    match (state, found) {
+        (_, true) => tr(Msg::HasStock, &[]),
+        (Some(SaleState::NotOnSale), _) => tr(Msg::NotOnSale, &[]),
+        // 场次有票, 但配置的票档都不可购买
+        (Some(SaleState::Available), _) => tr(Msg::GradesSoldOut, &[]),
+        (Some(SaleState::SoldOut), _) => tr(Msg::SoldOut, &[]),
+        (None, _) => String::new(),
+    }
+}
+
+// 命令行输出, 倒计时/查询库存在同一行刷新, 付款链接输出二维码
+#[derive(Debug, Default)]
+pub struct ConsoleSink;
+
+impl EventSink for ConsoleSink {
+    fn on_countdown(&self, remaining_ms: i64) {
+        print!("\r\t{}\t", countdown_text(remaining_ms));
+        let _ = io::stdout().flush();
+    }
+
+    fn on_stock_check(&self, round: u64) {
+        print!("\r\t{}", tr(Msg::StockCheckRound, &[&round]));
+        let _ = io::stdout().flush();
+    }
+
+    fn on_leak_check(&self, round: u64, state: Option<SaleState>, found: bool) {
+        print!(
+            "\r\t{}{}",
+            tr(Msg::QueryStock, &[&round]),
+            leak_check_text(state, found)
+        );
+        let _ = io::stdout().flush();
+    }
+
+    fn on_payment_link(&self, link: &str) {
+        println!("\n\t{}\n", tr(Msg::PaymentLink, &[&link]));
+        match QRBuilder::new(link).build() {
+            Ok(qrcode) => println!("{}\n", qrcode.to_str()),
+            Err(e) => warn!("生成付款二维码失败, 原因:{:?}", e),
+        }
+    }
+}
+
+// 通过日志输出, 不直接写stdout, 适合JSON日志及嵌入使用
+// 倒计时每分钟(最后10秒每秒)输出一次, 查询库存使用debug级别
+#[derive(Debug, Default)]
+pub struct LogSink {
+    // 上次输出倒计时的剩余秒数
+    last_countdown_secs: Mutex<Option<i64>>,
+}
+
+impl EventSink for LogSink {
+    fn on_countdown(&self, remaining_ms: i64) {
+        let secs = remaining_ms / 1000;
+        let mut last = self.last_countdown_secs.lock().unwrap();
+        if *last != Some(secs) && (secs % 60 == 0 || secs <= 10) {
+            *last = Some(secs);
+            info!("{}", countdown_text(remaining_ms));
+        }
+    }
+
+    fn on_stock_check(&self, round: u64) {
+        debug!("第{}次查询库存...", round);
+    }
+
+    fn on_leak_check(&self, round: u64, state: Option<SaleState>, found: bool) {
+        debug!(
+            "{}{}",
+            tr(Msg::QueryStock, &[&round]),
+            leak_check_text(state, found)
+        );
+    }
+
+    fn on_payment_link(&self, link: &str) {
+        info!("{}", tr(Msg::PaymentLink, &[&link]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn countdown_text_exactly_one_day() {
+        assert_eq!(
+            countdown_text(86_400_000),
+            tr(Msg::CountdownDays, &[&1, &0, &0, &"0.000"])
+        );
+        assert_eq!(
+            countdown_text(86_399_999),
+            tr(Msg::Countdown, &[&23, &59, &"59.999"])
+        );
+    }
+
+    #[test]
+    fn leak_check_text_states() {
+        assert_eq!(leak_check_text(Some(SaleState::Available), true), tr(Msg::HasStock, &[]));
+        assert_eq!(
+            leak_check_text(Some(SaleState::Available), false),
+            tr(Msg::GradesSoldOut, &[])
+        );
+        assert_eq!(leak_check_text(Some(SaleState::SoldOut), false), tr(Msg::SoldOut, &[]));
+        assert_eq!(leak_check_text(Some(SaleState::NotOnSale), false), tr(Msg::NotOnSale, &[]));
+        assert_eq!(leak_check_text(None, false), "");
+    }
+
+    #[test]
+    fn countdown_text_zero() {
+        assert_eq!(countdown_text(0), tr(Msg::Countdown, &[&0, &0, &"0.000"]));
+    }
+}
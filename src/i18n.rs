@@ -0,0 +1,376 @@
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+
+// 当前语言
+static LANG: AtomicU8 = AtomicU8::new(Lang::Zh as u8);
+
+// 输出语言
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Lang {
+    #[default]
+    Zh = 0,
+    En = 1,
+}
+
+// 设置输出语言, 程序启动时调用
+pub fn set_lang(lang: Lang) {
+    LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+// 当前输出语言
+pub fn lang() -> Lang {
+    match LANG.load(Ordering::Relaxed) {
+        1 => Lang::En,
+        _ => Lang::Zh,
+    }
+}
+
+// 定义文案及其中英文模板, 新增文案时必须同时提供两种语言
+macro_rules! messages {
+    ($($name:ident => $zh:expr, $en:expr;)*) => {
+        // 用户可见的提示文案, {}为参数占位符
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Msg {
+            $($name,)*
+        }
+
+        impl Msg {
+            // 所有文案, 由宏生成, 不会遗漏
+            #[cfg(test)]
+            const ALL: &'static [Msg] = &[$(Msg::$name,)*];
+
+            fn zh(self) -> &'static str {
+                match self {
+                    $(Self::$name => $zh,)*
+                }
+            }
+
+            fn en(self) -> &'static str {
+                match self {
+                    $(Self::$name => $en,)*
+                }
+            }
+        }
+    };
+}
+
+messages! {
+    Countdown =>
+        "开抢倒计时:{}小时:{}分钟:{}秒",
+        "Sale starts in: {}h:{}m:{}s";
+    CountdownDays =>
+        "开抢倒计时:{}天:{}小时:{}分钟:{}秒",
+        "Sale starts in: {}d:{}h:{}m:{}s";
+    Summary =>
+        "账号备注: {}\n\t账号昵称: {}\n\t门票名称: {}\n\t场次名称: {}\n\t票档名称: {}\n\t开售时间: {}",
+        "Account: {}\n\tNickname: {}\n\tTicket: {}\n\tSession: {}\n\tGrade: {}\n\tSale starts: {}";
+    CheckingUserInfo =>
+        "正在检查用户信息...",
+        "Checking user info...";
+    CookieExpired =>
+        "获取用户信息失败, cookie已过期, 请重新登陆!",
+        "Failed to get user info, cookie expired, please log in again!";
+    UserInfoFailed =>
+        "获取用户信息失败, 原因:{}",
+        "Failed to get user info, reason: {}";
+    FetchingTicketInfo =>
+        "正在获取演唱会信息...",
+        "Fetching ticket info...";
+    TicketInfoFailed =>
+        "获取演唱会信息失败, {}",
+        "Failed to get ticket info, {}";
+    FetchingPerformInfo =>
+        "正在获取场次/票档信息...",
+        "Fetching session/grade info...";
+    PerformInfoFailed =>
+        "获取场次:{}信息失败, {}, 尝试下一场次...",
+        "Failed to get session: {}, {}, trying next session...";
+    OrderBuilt =>
+        "成功生成订单...",
+        "Order built...";
+    OrderBuildFailed =>
+        "生成订单失败, {}",
+        "Failed to build order, {}";
+    OrderSubmitted =>
+        "提交订单成功, 请尽快前往手机APP付款",
+        "Order submitted, please pay in the Damai app as soon as possible";
+    OrderSubmitFailed =>
+        "提交订单失败, 原因:{}",
+        "Failed to submit order, reason: {}";
+    GradeSuccess =>
+        "票档:{}, 抢购成功!",
+        "Grade: {}, purchase succeeded!";
+    PurchasedTarget =>
+        "抢到的场次:{}, 票档:{}, 价格:{}",
+        "Secured session: {}, grade: {}, price: {}";
+    GradeFailed =>
+        "票档:{}, 抢购失败, 尝试下一票档...",
+        "Grade: {}, purchase failed, trying next grade...";
+    GradeSoldOut =>
+        "票档:{}, 已售空, 尝试下一票档...",
+        "Grade: {}, sold out, trying next grade...";
+    SoldOutPickUpLeaks =>
+        "商品已售空, 去捡漏...",
+        "Sold out, polling for returned tickets...";
+    NotGotPickUpLeaks =>
+        "未能抢到票, 去捡漏...",
+        "No ticket purchased, polling for returned tickets...";
+    PriorityPurchaseEnded =>
+        "优先购已结束, 等待正式开抢...",
+        "Priority purchase ended, waiting for general sale...";
+    QueryStock =>
+        "第{}次查询库存, ",
+        "Stock check #{}, ";
+    HasStock =>
+        "有余票...",
+        "tickets available...";
+    NotOnSale =>
+        "未开售...",
+        "not on sale yet...";
+    SoldOut =>
+        "已售罄...",
+        "sold out...";
+    GradesSoldOut =>
+        "配置票档无票...",
+        "no tickets for the configured grades...";
+    LeakFound =>
+        "场次:{}, 票档:{}, 有库存, 去购买...",
+        "Session: {}, grade: {}, in stock, buying...";
+    LeakSuccess =>
+        "场次:{}, 票档:{}, 捡漏成功!",
+        "Session: {}, grade: {}, returned ticket purchased!";
+    PaymentLink =>
+        "付款链接: {}",
+        "Payment link: {}";
+    RunResult =>
+        "运行结果:",
+        "Results:";
+    OutcomeSuccess =>
+        "账号:{}, 抢购成功, 请尽快前往手机APP付款!",
+        "Account: {}, purchase succeeded, please pay in the Damai app!";
+    OutcomeNotGot =>
+        "账号:{}, 未抢到票",
+        "Account: {}, no ticket purchased";
+    OutcomeStopped =>
+        "账号:{}, 其他账号已抢购成功, 已停止",
+        "Account: {}, stopped because another account succeeded";
+    OutcomeFailed =>
+        "账号:{}, 运行失败, 原因:{}",
+        "Account: {}, failed, reason: {}";
+    SubmitRetrying =>
+        "正在使用同一订单重试提交...",
+        "Retrying submission with the same order...";
+    AlreadyOrdered =>
+        "已有该演出的订单, 停止抢购, 请前往手机APP查看...",
+        "An order for this show already exists, stopped, please check it in the Damai app...";
+    AlreadyOrderedRef =>
+        "已有该演出的订单, 订单号:{}, 停止抢购, 请前往手机APP查看...",
+        "An order for this show already exists, order id: {}, stopped, please check it in the Damai app...";
+    CaptchaRequired =>
+        "触发滑块/验证码验证, 请尽快在手机APP/浏览器中完成验证",
+        "Slider/captcha verification triggered, please complete it in the Damai app/browser as soon as possible";
+    CaptchaRequiredUrl =>
+        "触发滑块/验证码验证, 请尽快完成验证, 验证地址:{}",
+        "Slider/captcha verification triggered, please complete it as soon as possible, url: {}";
+    CaptchaPause =>
+        "暂停抢购{}秒, 等待完成验证...",
+        "Paused for {}s, waiting for verification...";
+    SessionOutOfRange =>
+        "配置的场次(ticket.sessions) {} 超出范围, 共 {} 场",
+        "Configured session (ticket.sessions) {} is out of range, {} sessions in total";
+    SessionNoPerform =>
+        "配置的场次(ticket.sessions) {}:{} 没有可购买的演出, 可能为选座购票的演出, 暂不支持",
+        "Configured session (ticket.sessions) {}:{} has nothing to buy directly, it may require seat selection, which is not supported";
+    SessionNoPerformHint =>
+        "该场次没有可购买的演出, 可能为选座购票的演出",
+        "Nothing to buy directly in this session, it may require seat selection";
+    GradeOutOfRange =>
+        "配置的票档(ticket.grade) {} 超出范围, 共 {} 档",
+        "Configured grade (ticket.grade) {} is out of range, {} grades in total";
+    NoSkuFound =>
+        "未获取到场次/票档信息...",
+        "No session/grade info found...";
+    ViewerCountOutOfRange =>
+        "ticket.viewer_count:{}超出范围, 当前账号共有{}位实名观演人",
+        "ticket.viewer_count: {} is out of range, the account has {} real-name viewers";
+    ViewerCountSelected =>
+        "按viewer_count配置, 选择前{}位实名观演人...",
+        "Selecting the first {} real-name viewers per viewer_count...";
+    SplitBought =>
+        "跨票档凑票, {}, 已购买{}/{}张",
+        "Split across grades, {}, bought {}/{}";
+    SplitReduceNum =>
+        "票档:{}, 库存不足{}张, 减少购买数量...",
+        "Grade: {}, fewer than {} left, reducing quantity...";
+    SplitInterrupted =>
+        "跨票档凑票中断, 原因:{}",
+        "Split across grades interrupted, reason: {}";
+    SplitUnconfirmed =>
+        "{}, 提交订单成功但未返回订单号, 无法确认购买数量, 停止凑票, 请前往手机APP查看订单",
+        "{}, order submitted without an order id, cannot confirm the quantity, stopped splitting, please check your orders in the Damai app";
+    SplitFinished =>
+        "跨票档凑票结束, 共购买{}/{}张, 分配结果:",
+        "Split across grades finished, bought {}/{}, allocation:";
+    Allocation =>
+        "场次:{}, 票档:{}, 数量:{}, 订单号:{}",
+        "Session: {}, grade: {}, quantity: {}, order id: {}";
+    Unknown =>
+        "未知",
+        "unknown";
+    OverviewTicketName =>
+        "门票名称: {}",
+        "Ticket: {}";
+    OverviewSellStart =>
+        "开售时间: {}",
+        "Sale starts: {}";
+    OverviewSession =>
+        "场次{}: {}",
+        "Session {}: {}";
+    OverviewIndex =>
+        "序号",
+        "No.";
+    OverviewGradeName =>
+        "票档名称",
+        "Grade";
+    OverviewPrice =>
+        "价格",
+        "Price";
+    OverviewSalable =>
+        "可购买",
+        "Available";
+    Yes =>
+        "是",
+        "yes";
+    No =>
+        "否",
+        "no";
+    BuyNumZero =>
+        "购买数量不能为0, 请检查ticket.num配置",
+        "Quantity must not be 0, please check ticket.num";
+    BuyNumCapped =>
+        "购买数量:{}超出限购数量:{}, 按限购数量购买...",
+        "Quantity {} exceeds the purchase limit {}, buying the limit instead...";
+    BuyNum =>
+        "购买数量:{}",
+        "Quantity: {}";
+    OrderSummary =>
+        "订单概要, {}",
+        "Order summary, {}";
+    OrderSummaryText =>
+        "商品:{}, 订单金额:{}",
+        "Items: {}, total: {}";
+    CookieExpiredNotify =>
+        "cookie已过期, 请重新登陆!",
+        "Cookie expired, please log in again!";
+    SessionRefreshed =>
+        "会话刷新成功...",
+        "Session refreshed...";
+    SessionExpiredRerun =>
+        "会话已过期, 正在刷新会话并重新运行...",
+        "Session expired, refreshing the session and running again...";
+    SessionRefreshing =>
+        "会话失效, 正在刷新会话...",
+        "Session invalid, refreshing the session...";
+    LeakDisabledExit =>
+        "未能抢到票, 捡漏已关闭, 退出...",
+        "No ticket purchased and pick-up of returns is disabled, exiting...";
+    StockCheckRound =>
+        "第{}次查询库存...",
+        "Stock check #{}...";
+    OutcomeTips =>
+        "如遇到错误:[哎哟喂,被挤爆啦,请稍后重试], 请不要再重试, 请先检查:\n\t1.是否使用了大厂服务器?\n\t2.cookies是否复制完整?\n\t3.是否频繁运行程序导致触发了频繁验证? 请重新获取cookie之后再尝试!\n\t4.ticket.retry_times不宜设置过大。\n\t5.捡漏轮询间隔(pick_up_leaks.interval)不宜设置过小, 轮询次数(pick_up_leaks.times)不宜设置过大!",
+        "If you see the error [哎哟喂,被挤爆啦,请稍后重试], do not retry right away, first check:\n\t1. Are you running on a cloud provider's server?\n\t2. Was the cookie copied completely?\n\t3. Has running too often triggered verification? Get a fresh cookie and try again!\n\t4. ticket.retry_times should not be too large.\n\t5. The pick-up polling interval (pick_up_leaks.interval) should not be too small, nor the poll count (pick_up_leaks.times) too large!";
+    TuiTitle =>
+        " dm-ticket, CTRL-C退出 ",
+        " dm-ticket, CTRL-C to quit ";
+    TuiLogs =>
+        " 日志 ",
+        " Logs ";
+    TuiAccount =>
+        "账号",
+        "Account";
+    TuiCountdown =>
+        "倒计时",
+        "Countdown";
+    TuiAttempts =>
+        "抢购次数",
+        "Attempts";
+    TuiLatency =>
+        "耗时",
+        "Latency";
+    TuiStatus =>
+        "状态",
+        "Status";
+    Millis =>
+        "{}毫秒",
+        "{}ms";
+    StatusWaiting =>
+        "等待开抢",
+        "Waiting for sale";
+    StatusRetrying =>
+        "第{}次抢购未成功, 重试中",
+        "Attempt #{} failed, retrying";
+    StatusAttemptSuccess =>
+        "第{}次抢购提交订单成功",
+        "Attempt #{} submitted the order";
+    StatusStockCheck =>
+        "监控模式, 第{}次查询库存",
+        "Monitoring, stock check #{}";
+    StatusLeakCheck =>
+        "捡漏, 第{}次查询库存, {}",
+        "Polling for returns, stock check #{}, {}";
+    StatusSubmitted =>
+        "提交订单成功, 请尽快付款",
+        "Order submitted, please pay soon";
+    StatusSuccess =>
+        "抢购成功, {} {}",
+        "Purchased, {} {}";
+}
+
+impl Msg {
+    // 文案模板
+    pub fn template(self) -> &'static str {
+        match lang() {
+            Lang::Zh => self.zh(),
+            Lang::En => self.en(),
+        }
+    }
+}
+
+// 按当前语言渲染文案, 依次替换{}占位符
+pub fn tr(msg: Msg, args: &[&dyn Display]) -> String {
+    let mut parts = msg.template().split("{}");
+    let mut text = parts.next().unwrap_or_default().to_string();
+    let mut args = args.iter();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            text.push_str(&arg.to_string());
+        }
+        text.push_str(part);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_msg_has_both_languages() {
+        for msg in Msg::ALL {
+            let (zh, en) = (msg.zh(), msg.en());
+            assert!(!zh.is_empty() && !en.is_empty(), "{:?}缺少翻译", msg);
+            assert_eq!(
+                zh.matches("{}").count(),
+                en.matches("{}").count(),
+                "{:?}中英文的参数数量不一致",
+                msg
+            );
+        }
+    }
+}
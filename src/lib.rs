@@ -1,6 +1,63 @@
+//! 大麦抢票库, 可嵌入其他异步程序中使用, 命令行程序(dm-ticket)只是对本库的简单封装。
+//!
+//! 主要入口:
+//! - [`DmTicket`]: 单个账号的抢购流程, [`DmTicket::run`]运行完整流程(检查cookie、获取门票信息、等待开抢、抢购、捡漏),
+//!   也可单独调用[`DmTicket::get_ticket_info`]、[`DmTicket::buy_it_now`]、[`DmTicket::wait_for_buy`]、[`DmTicket::pick_up_leaks`]等方法组合使用
+//! - [`Account`]: 账号配置, 可从配置文件加载([`load_global_config`]), 也可在代码中构造
+//! - [`runner::run_accounts`]: 并发运行多个账号
+//!
+//! 嵌入使用时建议关闭[`DmTicket::listen_ctrl_c`], 通过[`DmTicket::cancel_token`]停止抢购:
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! use dm_ticket::{load_global_config, DmTicket};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let config = load_global_config(Path::new("config/config.yaml")).expect("加载配置失败");
+//! let account = config.accounts.into_iter().next().expect("未配置账号");
+//!
+//! let mut dm_ticket = DmTicket::new(account).await?;
+//! dm_ticket.listen_ctrl_c = false;
+//! let cancel_token = dm_ticket.cancel_token.clone();
+//! // 在其他任务中调用cancel_token.cancel()即可停止抢购
+//! let purchased = dm_ticket.run().await?;
+//! if purchased {
+//!     println!("订单号:{:?}", dm_ticket.order_result().and_then(|result| result.order_id));
+//! }
+//! # drop(cancel_token);
+//! # Ok(())
+//! # }
+//! ```
+
+// 命令行程序(dm-ticket)的入口及参数, 不属于库的接口
+#[doc(hidden)]
+pub mod app;
+pub mod cache;
+#[doc(hidden)]
+pub mod cli;
 pub mod clients;
 pub mod config;
+pub mod dump;
 pub mod error;
+pub mod events;
+pub mod i18n;
+pub mod logger;
 pub mod login;
 pub mod models;
+pub mod notify;
+pub mod record;
+pub mod runner;
+pub mod selftest;
+pub mod stats;
+#[cfg(test)]
+mod testing;
 pub mod ticket;
+pub mod time;
+#[cfg(feature = "tui")]
+pub mod tui;
+
+pub use config::{load_global_config, Account, Config, Ticket};
+pub use error::{DmApiError, DmTicketError};
+pub use models::order::SubmitOrderResult;
+pub use ticket::{DmTicket, PurchasedTarget};
@@ -0,0 +1,147 @@
+use chrono::{DateTime, Local};
+use log::{info, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{cell::RefCell, env, io::Write, sync::RwLock};
+
+// 日志格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+// 日志级别
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+// 本项目日志的模块前缀, 其余依赖库只输出警告及以上日志
+const CRATE_MODULE: &str = "dm_ticket";
+
+tokio::task_local! {
+    // 当前任务所属的账号备注
+    pub static ACCOUNT_REMARK: String;
+}
+
+thread_local! {
+    // 当前日志记录附带的结构化字段
+    static EVENT_FIELDS: RefCell<Option<Value>> = RefCell::new(None);
+}
+
+// 日志转发函数
+pub type LogHook = Box<dyn Fn(&Record) + Send + Sync>;
+
+// 设置后日志交给转发函数处理(如终端仪表盘显示), 不再输出到终端
+static LOG_HOOK: RwLock<Option<LogHook>> = RwLock::new(None);
+
+// 设置日志转发, 传入None时恢复输出到终端
+pub fn set_hook(hook: Option<LogHook>) {
+    *LOG_HOOK.write().unwrap() = hook;
+}
+
+// 支持转发的日志, 未设置转发时使用内部日志输出
+struct HookLogger<L> {
+    inner: L,
+}
+
+impl<L: Log> Log for HookLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.inner.enabled(record.metadata()) {
+            return;
+        }
+        match LOG_HOOK.read().unwrap().as_ref() {
+            Some(hook) => hook(record),
+            None => self.inner.log(record),
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
+
+// 记录关键事件, JSON格式下附带结构化字段
+pub fn event(name: &str, fields: Value, message: &str) {
+    EVENT_FIELDS.with(|f| *f.borrow_mut() = Some(json!({ "event": name, "data": fields })));
+    info!("{}", message);
+    EVENT_FIELDS.with(|f| *f.borrow_mut() = None);
+}
+
+// 将日志记录格式化为JSON
+fn format_json(record: &Record) -> Value {
+    let local: DateTime<Local> = Local::now();
+    let account = ACCOUNT_REMARK.try_with(|remark| remark.clone()).ok();
+    let mut value = json!({
+        "timestamp": local.to_rfc3339(),
+        "level": record.level().to_string(),
+        "target": record.target(),
+        "account": account,
+        "message": record.args().to_string(),
+    });
+    if let Some(fields) = EVENT_FIELDS.with(|f| f.borrow().clone()) {
+        value["fields"] = fields;
+    }
+    value
+}
+
+// 是否通过RUST_LOG环境变量配置了日志过滤规则
+fn has_env_filters() -> bool {
+    env::var("RUST_LOG").map_or(false, |filters| !filters.trim().is_empty())
+}
+
+// 初始化日志, 配置了RUST_LOG时按RUST_LOG过滤(支持按模块过滤), 否则使用level
+pub fn init(format: LogFormat, level: LevelFilter) {
+    let mut builder = pretty_env_logger::formatted_builder();
+    match env::var("RUST_LOG") {
+        Ok(filters) if !filters.trim().is_empty() => {
+            builder.parse_filters(&filters);
+        }
+        _ => {
+            // 依赖库(reqwest/hyper等)的调试日志过多, 只输出警告及以上
+            builder
+                .filter_level(LevelFilter::Warn)
+                .filter_module(CRATE_MODULE, LevelFilter::Trace);
+        }
+    }
+    if format == LogFormat::Json {
+        builder.format(|buf, record| writeln!(buf, "{}", format_json(record)));
+    }
+    let logger = builder.build();
+    let max_level = logger.filter();
+    log::set_boxed_logger(Box::new(HookLogger { inner: logger }))
+        .expect("日志只能初始化一次");
+    log::set_max_level(max_level);
+    set_level(level);
+}
+
+// 修改日志级别, 配置了RUST_LOG时不修改
+pub fn set_level(level: LevelFilter) {
+    if !has_env_filters() {
+        log::set_max_level(level);
+    }
+}
@@ -4,9 +4,14 @@ pub mod qrcode;
 pub mod ticket;
 pub mod user;
 
+use anyhow::{anyhow, Result};
 use chrono::{DateTime, Local};
-use serde::{Deserialize, Serialize};
+use log::debug;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{value, Value};
+
+use crate::clients::endpoints::DEFAULT_APP_KEY;
+use std::path::PathBuf;
 // cookie token.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DmToken {
@@ -15,6 +20,25 @@ pub struct DmToken {
     pub enc_token: String,
 }
 
+// 接口调用成功的ret前缀, 后缀的提示信息可能变化, 如: SUCCESS::接口调用成功
+pub const SUCCESS_PREFIX: &str = "SUCCESS::";
+
+// 根据ret判断接口是否调用成功, 只匹配第一项的SUCCESS::前缀, 忽略提示信息及之后的附加项
+pub fn is_success(ret: &[String]) -> bool {
+    ret.first()
+        .map_or(false, |r| r.trim_start().starts_with(SUCCESS_PREFIX))
+}
+
+// 返回数据的解析位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataField {
+    // 解析整个data
+    Data,
+
+    // 解析data.result
+    Result,
+}
+
 // 大麦API返回数据
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DmRes {
@@ -22,6 +46,44 @@ pub struct DmRes {
     pub data: value::Value,
     pub ret: Vec<String>,
     pub v: Option<String>,
+
+    // 原始响应的保存路径, 开启--debug-dump时设置
+    #[serde(skip)]
+    pub dump_path: Option<PathBuf>,
+}
+
+impl DmRes {
+    // 接口是否调用成功
+    pub fn is_success(&self) -> bool {
+        is_success(&self.ret)
+    }
+
+    // 按解析位置反序列化返回数据
+    pub fn parse<T: DeserializeOwned>(&self, field: DataField) -> Result<T> {
+        match field {
+            DataField::Data => serde_json::from_value(self.data.clone()).map_err(|e| {
+                debug!("解析data失败:{:?}", self);
+                anyhow!("解析data失败, {}", e)
+            }),
+            DataField::Result => self.result(),
+        }
+    }
+
+    // 解析data.result, 兼容JSON字符串及对象两种格式
+    pub fn result<T: DeserializeOwned>(&self) -> Result<T> {
+        let result = match &self.data["result"] {
+            Value::String(s) => serde_json::from_str(s),
+            Value::Object(_) => serde_json::from_value(self.data["result"].clone()),
+            _ => {
+                debug!("返回数据缺少result字段:{:?}", self);
+                return Err(anyhow!("返回数据缺少result字段, 结果:{:?}", self.ret));
+            }
+        };
+        result.map_err(|e| {
+            debug!("解析result失败:{:?}", self);
+            anyhow!("解析result失败, {}", e)
+        })
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -74,7 +136,7 @@ impl CommonParams {
 
         Self {
             jsv: "2.7.2",
-            app_key: "12574478",
+            app_key: DEFAULT_APP_KEY,
             r#type: "originaljson",
             data_type: "json",
             h5_request: "true",
@@ -91,3 +153,23 @@ impl Default for CommonParams {
         Self::build()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ret(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn is_success_by_ret() {
+        assert!(is_success(&ret(&["SUCCESS::调用成功"])));
+        assert!(is_success(&ret(&["SUCCESS::接口调用成功", "extra"])));
+        assert!(!is_success(&ret(&["FAIL_SYS_TOKEN_EXOIRED::令牌过期"])));
+        assert!(!is_success(&ret(&["RGV587_ERROR::SM::哎哟喂,被挤爆啦,请稍后重试"])));
+        // 只看第一项
+        assert!(!is_success(&ret(&["FAIL_SYS_SESSION_EXPIRED::Session过期", "SUCCESS::调用成功"])));
+        assert!(!is_success(&[]));
+    }
+}
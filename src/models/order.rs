@@ -1,8 +1,11 @@
+use std::fmt;
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
 use super::CommonParams;
+use crate::i18n::{tr, Msg};
 
 pub struct OrderParams;
 
@@ -109,6 +112,53 @@ pub struct OrderInfo {
     pub linkage: OrderInfoLinkage,
 }
 
+// 订单概要, 从生成订单返回的data中解析
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct OrderSummary {
+    // 订单总金额
+    pub total_price: Option<String>,
+
+    // 商品名称
+    pub items: Vec<String>,
+}
+
+impl fmt::Display for OrderSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unknown = tr(Msg::Unknown, &[]);
+        let items = match self.items.is_empty() {
+            true => unknown.clone(),
+            false => self.items.join(", "),
+        };
+        let total_price = self.total_price.clone().unwrap_or(unknown);
+        write!(f, "{}", tr(Msg::OrderSummaryText, &[&items, &total_price]))
+    }
+}
+
+impl OrderInfo {
+    // 解析订单金额及商品名称, data中的组件以"组件类型_ID"为key, 字段缺失时返回None
+    pub fn summary(&self) -> OrderSummary {
+        let mut summary = OrderSummary::default();
+        let components = match self.data.as_object() {
+            Some(components) => components,
+            None => return summary,
+        };
+        for (key, component) in components {
+            let fields = &component["fields"];
+            if summary.total_price.is_none()
+                && (key.starts_with("realPay") || key.starts_with("submitOrder"))
+            {
+                summary.total_price = field_string(fields, &["price", "totalPrice", "realPay"]);
+            }
+            if key.starts_with("item_") || key.starts_with("dmItemInfo") {
+                if let Some(title) = field_string(fields, &["title", "itemName"]) {
+                    summary.items.push(title);
+                }
+            }
+        }
+        summary
+    }
+}
+
 pub struct SubmitOrderParams;
 
 impl SubmitOrderParams {
@@ -127,3 +177,45 @@ impl SubmitOrderParams {
         Ok(params)
     }
 }
+
+// 提交订单结果
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SubmitOrderResult {
+    // 订单号
+    pub order_id: Option<String>,
+
+    // 支付链接
+    pub pay_url: Option<String>,
+
+    // 订单总金额, 从生成订单的数据中解析
+    pub total_price: Option<String>,
+}
+
+// 读取字符串或数字字段
+fn field_string(data: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| match &data[*key] {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+impl SubmitOrderResult {
+    // 从提交订单返回的data中解析订单号及支付链接
+    pub fn from_data(data: &Value) -> Self {
+        Self {
+            order_id: field_string(data, &["bizOrderId", "orderId", "alipayOrderId"]),
+            pay_url: field_string(data, &["alipayWapCashierUrl", "payUrl"]),
+            total_price: None,
+        }
+    }
+
+    // 付款链接, 优先使用返回的支付链接, 否则拼接订单详情页地址
+    pub fn payment_link(&self) -> Option<String> {
+        self.pay_url.clone().or_else(|| {
+            self.order_id.as_ref().map(|id| {
+                format!("https://m.damai.cn/damai/orderdetail/index.html?orderId={}", id)
+            })
+        })
+    }
+}
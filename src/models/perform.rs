@@ -1,5 +1,7 @@
 use anyhow::Result;
-use serde::{Deserialize, Serialize};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::{json, Value};
 
 use super::CommonParams;
@@ -37,7 +39,7 @@ impl PerformForm {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Sku {
     #[serde(rename = "skuId")]
     pub sku_id: String,
@@ -51,10 +53,66 @@ pub struct Sku {
     #[serde(rename = "skuSalable")]
     pub sku_salable: String,
 
-    pub price: String,
+    // 票档价格, 兼容字符串("1280.00")及数字格式, 无法解析时为None
+    #[serde(default, deserialize_with = "deserialize_price")]
+    pub price: Option<f64>,
+
+    // 所属场次名称, 不在接口返回中, 获取场次信息后填充
+    #[serde(skip)]
+    pub perform_name: String,
+}
+
+// 解析价格, 无法解析时返回None, 不影响整个票档的反序列化
+fn deserialize_price<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    Ok(match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    })
+}
+
+// 票档名称中的价格, 只匹配紧跟货币符号(¥/￥)或"元"的数字, 避免匹配"VIP2区"等区域编号
+static PRICE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[¥￥]\s*(\d+(?:\.\d+)?)|(\d+(?:\.\d+)?)\s*元").unwrap());
+
+impl Sku {
+    // 票档价格, price缺失时从票档名称中解析, 如: "内场 1280元"
+    pub fn price_value(&self) -> Option<f64> {
+        self.price.or_else(|| {
+            let caps = PRICE_RE.captures(&self.price_name)?;
+            caps.get(1)
+                .or_else(|| caps.get(2))?
+                .as_str()
+                .parse::<f64>()
+                .ok()
+        })
+    }
+
+    // 用于显示的价格, 如: 1280.00
+    pub fn price_text(&self) -> String {
+        match self.price_value() {
+            Some(price) => format!("{:.2}", price),
+            None => "未知".to_string(),
+        }
+    }
+}
+
+// 场次售卖状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaleState {
+    // 未到开售时间
+    NotOnSale,
+    // 已开售, 无可购买的票档
+    SoldOut,
+    // 有可购买的票档
+    Available,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Perform {
     #[serde(rename = "performId")]
     pub perform_id: String,
@@ -66,7 +124,44 @@ pub struct Perform {
     pub sku_list: Vec<Sku>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Perform {
+    // 售卖状态, 未到开售时间时为NotOnSale(此时票档的skuSalable同样为false), 否则按票档是否可购买判断
+    pub fn sale_state(&self, sell_start_timestamp: Option<i64>, now_millis: i64) -> SaleState {
+        if sell_start_timestamp.map_or(false, |start| now_millis < start) {
+            return SaleState::NotOnSale;
+        }
+        match self.sku_list.iter().any(|sku| sku.sku_salable.contains("true")) {
+            true => SaleState::Available,
+            false => SaleState::SoldOut,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PerformInfo {
     pub perform: Perform,
 }
+
+impl PerformInfo {
+    // 将场次名称填充到各票档, 便于按票档购买后得知所属场次
+    pub fn fill_perform_name(&mut self) {
+        for sku in self.perform.sku_list.iter_mut() {
+            sku.perform_name = self.perform.perform_name.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing;
+
+    #[test]
+    fn price_value_from_name() {
+        assert_eq!(testing::sku("1", "内场 1280元").price_value(), Some(1280.0));
+        assert_eq!(testing::sku("1", "看台 ¥680.50").price_value(), Some(680.5));
+        assert_eq!(testing::sku("1", "看台￥ 380").price_value(), Some(380.0));
+        // 区域编号不是价格
+        assert_eq!(testing::sku("1", "VIP2区 1680元").price_value(), Some(1680.0));
+        assert_eq!(testing::sku("1", "VIP2区").price_value(), None);
+    }
+}
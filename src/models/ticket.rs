@@ -5,7 +5,7 @@ use serde_json::Value;
 use super::CommonParams;
 
 // 查询门票信息表单
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TicketInfoForm {
     #[serde(rename = "itemId")]
     item_id: String,
@@ -24,7 +24,7 @@ impl TicketInfoForm {
 }
 
 // 查询门票信息参数
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TicketInfoParams {}
 impl TicketInfoParams {
     pub fn build() -> Result<Value> {
@@ -35,7 +35,7 @@ impl TicketInfoParams {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Sku {
     #[serde(rename = "skuId")]
     pub sku_id: String,
@@ -44,7 +44,7 @@ pub struct Sku {
     pub sku_name: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Perform {
     #[serde(rename = "performId")]
     pub perform_id: String, // 演出ID
@@ -59,7 +59,7 @@ pub struct Perform {
                               // pub sku_list: Vec<Sku>, // sku 列表
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PerformBase {
     pub name: String,
 
@@ -73,7 +73,7 @@ pub struct PerformBase {
     pub performs: Vec<Perform>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TicketDetail {
     #[serde(rename = "sellStartTime")]
     pub sell_start_timestamp: String,
@@ -83,9 +83,27 @@ pub struct TicketDetail {
 
     #[serde(rename = "performBases")]
     pub perform_bases: Vec<PerformBase>, // 演出场次列表, 账号设置选择索引
+
+    #[serde(rename = "limitQuantity", default)]
+    pub limit_quantity: Option<Value>, // 每单/每人限购数量, 可能为数字或字符串
+}
+
+impl TicketDetail {
+    // 限购数量, 未返回或为0时不限制
+    pub fn purchase_limit(&self) -> Option<usize> {
+        let limit = match self.limit_quantity.as_ref()? {
+            Value::Number(n) => n.as_u64()? as usize,
+            Value::String(s) => s.trim().parse::<usize>().ok()?,
+            _ => return None,
+        };
+        match limit {
+            0 => None,
+            limit => Some(limit),
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StaticDataItemBase {
     #[serde(rename = "itemId")]
     pub item_id: String,
@@ -94,13 +112,13 @@ pub struct StaticDataItemBase {
     pub item_name: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StaticData {
     #[serde(rename = "itemBase")]
     pub item_base: StaticDataItemBase,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DetailViewComponentItem {
     #[serde(rename = "staticData")]
     pub static_data: StaticData,
@@ -111,13 +129,13 @@ pub struct DetailViewComponentItem {
     pub item: TicketDetail,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DetailViewComponentMap {
     pub atmosphere: Value,
     pub item: DetailViewComponentItem,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TicketInfo {
     #[serde(rename = "detailViewComponentMap")]
     pub detail_view_component_map: DetailViewComponentMap,
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use log::info;
+use reqwest::Client;
+use serde_json::json;
+use std::time::Duration;
+
+use super::{Notifier, NotifyMessage};
+
+// 通知请求超时时间
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Bark通知
+#[derive(Debug)]
+pub struct BarkNotifier {
+    pub client: Client,
+    // 推送地址, 如: https://api.day.app/your_key
+    pub bark_url: String,
+}
+
+impl BarkNotifier {
+    pub fn new(bark_url: &str) -> Result<Self> {
+        let client = reqwest::Client::builder().timeout(TIMEOUT).build()?;
+        Ok(Self {
+            client,
+            bark_url: bark_url.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for BarkNotifier {
+    fn name(&self) -> &str {
+        "Bark"
+    }
+
+    async fn notify(&self, message: &NotifyMessage) -> Result<()> {
+        let data = json!({
+            "title": message.title(),
+            "body": message.content(),
+            "group": "dm-ticket",
+        });
+        self.client
+            .post(&self.bark_url)
+            .json(&data)
+            .send()
+            .await?
+            .error_for_status()?;
+        info!("Bark通知发送成功...");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn notify_payload() {
+        let (url, request) = testing::serve_once("{}");
+        let notifier = BarkNotifier::new(&format!("{}/barkkey/", url)).unwrap();
+        let message = NotifyMessage::new("测试账号", "演唱会", "2024-06-15 周六 19:30", "看台 680元");
+
+        notifier.notify(&message).await.unwrap();
+
+        let request = request.recv().unwrap();
+        assert!(request.starts_with("POST /barkkey "));
+        let body: Value = serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["title"], "[测试账号]大麦抢票成功");
+        assert_eq!(body["group"], "dm-ticket");
+        assert!(body["body"].as_str().unwrap().contains("票档名称: 看台 680元"));
+    }
+}
@@ -0,0 +1,47 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use log::info;
+use notify_rust::Notification;
+
+use super::{Notifier, NotifyKind, NotifyMessage};
+
+// 桌面通知, 使用系统原生通知弹窗, 适合本地电脑运行
+#[derive(Debug, Default)]
+pub struct DesktopNotifier;
+
+impl DesktopNotifier {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl Notifier for DesktopNotifier {
+    fn name(&self) -> &str {
+        "桌面"
+    }
+
+    async fn notify(&self, message: &NotifyMessage) -> Result<()> {
+        let summary = message.title();
+        let body = match message.kind {
+            NotifyKind::Success => format!(
+                "{}\n{}\n提交订单成功, 请立即前往手机APP付款!",
+                message.ticket_name, message.sku_name
+            ),
+            _ => message.content(),
+        };
+        // 部分平台发送通知为阻塞调用, 放到阻塞线程中执行
+        tokio::task::spawn_blocking(move || {
+            Notification::new()
+                .appname("dm-ticket")
+                .summary(&summary)
+                .body(&body)
+                .show()
+                .map(|_| ())
+                .map_err(|e| anyhow!("{}", e))
+        })
+        .await??;
+        info!("桌面通知发送成功...");
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use log::info;
+use std::time::Duration;
+
+use super::{Notifier, NotifyMessage};
+use crate::config::EmailConfig;
+
+// 通知请求超时时间
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// SSL端口, 其余端口使用STARTTLS
+const SMTP_SSL_PORT: u16 = 465;
+
+// 邮件通知
+pub struct EmailNotifier {
+    pub mailer: AsyncSmtpTransport<Tokio1Executor>,
+    pub from: Mailbox,
+    pub to: Mailbox,
+}
+
+impl EmailNotifier {
+    pub fn new(config: &EmailConfig) -> Result<Self> {
+        let builder = match config.port {
+            SMTP_SSL_PORT => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+            _ => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+        };
+        let mailer = builder
+            .port(config.port)
+            .credentials(Credentials::new(
+                config.username.clone(),
+                config.password.clone(),
+            ))
+            .timeout(Some(TIMEOUT))
+            .build();
+
+        let from = config.from.as_ref().unwrap_or(&config.username).parse()?;
+        let to = config.to.parse()?;
+
+        Ok(Self { mailer, from, to })
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &str {
+        "邮件"
+    }
+
+    async fn notify(&self, message: &NotifyMessage) -> Result<()> {
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(self.to.clone())
+            .subject(message.title())
+            .header(ContentType::TEXT_PLAIN)
+            .body(message.content())?;
+        self.mailer.send(email).await?;
+        info!("邮件通知发送成功...");
+        Ok(())
+    }
+}
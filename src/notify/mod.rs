@@ -0,0 +1,199 @@
+pub mod bark;
+#[cfg(feature = "desktop-notify")]
+pub mod desktop;
+pub mod email;
+pub mod server_chan;
+pub mod telegram;
+pub mod webhook;
+
+use crate::config::NotifyConfig;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use futures::future::join_all;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use self::{
+    bark::BarkNotifier, email::EmailNotifier, server_chan::ServerChanNotifier, telegram::TelegramNotifier,
+    webhook::WebhookNotifier,
+};
+
+// 通知类型
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyKind {
+    // 抢购成功
+    Success,
+
+    // 监控到有票
+    Available,
+
+    // 异常, 如cookie过期
+    Error,
+}
+
+// 通知内容
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NotifyMessage {
+    // 通知类型
+    pub kind: NotifyKind,
+
+    // 账号备注
+    pub remark: String,
+
+    // 门票名称
+    pub ticket_name: String,
+
+    // 场次名称
+    pub perform_name: String,
+
+    // 票档名称
+    pub sku_name: String,
+
+    // 票档价格
+    pub price: Option<String>,
+
+    // 订单总金额
+    pub total_price: Option<String>,
+
+    // 下单/通知时间
+    pub order_time: String,
+
+    // 错误信息
+    pub error: Option<String>,
+}
+
+impl NotifyMessage {
+    pub fn new(remark: &str, ticket_name: &str, perform_name: &str, sku_name: &str) -> Self {
+        let local: DateTime<Local> = Local::now();
+        Self {
+            kind: NotifyKind::Success,
+            remark: remark.to_string(),
+            ticket_name: ticket_name.to_string(),
+            perform_name: perform_name.to_string(),
+            sku_name: sku_name.to_string(),
+            price: None,
+            total_price: None,
+            order_time: local.format("%Y-%m-%d %H:%M:%S").to_string(),
+            error: None,
+        }
+    }
+
+    // 有票通知
+    pub fn available(
+        remark: &str,
+        ticket_name: &str,
+        perform_name: &str,
+        sku_name: &str,
+        price: &str,
+    ) -> Self {
+        let mut message = Self::new(remark, ticket_name, perform_name, sku_name);
+        message.kind = NotifyKind::Available;
+        message.price = Some(price.to_string());
+        message
+    }
+
+    // 异常通知, 如cookie过期
+    pub fn error(remark: &str, reason: &str) -> Self {
+        let mut message = Self::new(remark, "", "", "");
+        message.kind = NotifyKind::Error;
+        message.error = Some(reason.to_string());
+        message
+    }
+
+    // 通知标题
+    pub fn title(&self) -> String {
+        match self.kind {
+            NotifyKind::Success => format!("[{}]大麦抢票成功", self.remark),
+            NotifyKind::Available => format!("[{}]大麦有票啦", self.remark),
+            NotifyKind::Error => format!("[{}]大麦抢票异常", self.remark),
+        }
+    }
+
+    // 通知正文
+    pub fn content(&self) -> String {
+        match self.kind {
+            NotifyKind::Success => format!(
+                "账号备注: {}\n门票名称: {}\n场次名称: {}\n票档名称: {}\n订单金额: {}\n下单时间: {}\n提交订单成功, 请尽快前往手机APP付款, 超时订单将自动取消!",
+                self.remark,
+                self.ticket_name,
+                self.perform_name,
+                self.sku_name,
+                self.total_price.as_deref().unwrap_or("未知"),
+                self.order_time
+            ),
+            NotifyKind::Available => format!(
+                "账号备注: {}\n门票名称: {}\n场次名称: {}\n票档名称: {}\n票档价格: {}\n通知时间: {}\n票档已开放购买, 请尽快前往购买!",
+                self.remark,
+                self.ticket_name,
+                self.perform_name,
+                self.sku_name,
+                self.price.as_deref().unwrap_or_default(),
+                self.order_time
+            ),
+            NotifyKind::Error => format!(
+                "账号备注: {}\n异常原因: {}\n通知时间: {}\n请及时处理!",
+                self.remark,
+                self.error.as_deref().unwrap_or_default(),
+                self.order_time
+            ),
+        }
+    }
+}
+
+// 通知渠道
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    // 通知渠道名称, 用于日志
+    fn name(&self) -> &str;
+
+    async fn notify(&self, message: &NotifyMessage) -> Result<()>;
+}
+
+// 并发发送到所有通知渠道, 单个渠道失败不影响其他渠道
+pub async fn notify_all(notifiers: &[Box<dyn Notifier>], message: &NotifyMessage) {
+    let results = join_all(notifiers.iter().map(|notifier| notifier.notify(message))).await;
+    for (notifier, result) in notifiers.iter().zip(results) {
+        if let Err(e) = result {
+            warn!("{}通知发送失败, 原因:{:?}", notifier.name(), e);
+        }
+    }
+}
+
+// 根据配置生成通知渠道
+pub fn build_notifiers(config: &NotifyConfig) -> Result<Vec<Box<dyn Notifier>>> {
+    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+
+    if let Some(url) = &config.webhook {
+        notifiers.push(Box::new(WebhookNotifier::new(url)?));
+    }
+
+    if let Some(telegram) = &config.telegram {
+        notifiers.push(Box::new(TelegramNotifier::new(
+            &telegram.bot_token,
+            &telegram.chat_id,
+        )?));
+    }
+
+    if let Some(push_key) = &config.push_key {
+        notifiers.push(Box::new(ServerChanNotifier::new(push_key)?));
+    }
+
+    if let Some(bark_url) = &config.bark_url {
+        notifiers.push(Box::new(BarkNotifier::new(bark_url)?));
+    }
+
+    if let Some(email) = &config.email {
+        notifiers.push(Box::new(EmailNotifier::new(email)?));
+    }
+
+    if config.desktop {
+        #[cfg(feature = "desktop-notify")]
+        notifiers.push(Box::new(desktop::DesktopNotifier::new()));
+        #[cfg(not(feature = "desktop-notify"))]
+        warn!("未启用desktop-notify功能, 忽略桌面通知配置, 请使用--features desktop-notify重新编译");
+    }
+
+    Ok(notifiers)
+}
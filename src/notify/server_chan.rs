@@ -0,0 +1,98 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use log::info;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use super::{Notifier, NotifyMessage};
+
+// 通知请求超时时间
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Server酱接口地址
+const API_BASE: &str = "https://sctapi.ftqq.com";
+
+// Server酱通知
+#[derive(Debug)]
+pub struct ServerChanNotifier {
+    pub client: Client,
+    pub push_key: String,
+    // 接口地址, 默认为Server酱官方地址
+    pub api_base: String,
+}
+
+impl ServerChanNotifier {
+    pub fn new(push_key: &str) -> Result<Self> {
+        let client = reqwest::Client::builder().timeout(TIMEOUT).build()?;
+        Ok(Self {
+            client,
+            push_key: push_key.to_string(),
+            api_base: API_BASE.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for ServerChanNotifier {
+    fn name(&self) -> &str {
+        "Server酱"
+    }
+
+    async fn notify(&self, message: &NotifyMessage) -> Result<()> {
+        let url = format!("{}/{}.send", self.api_base, self.push_key);
+        // Server酱使用markdown, 换行需要两个换行符
+        let form = json!({
+            "title": message.title(),
+            "desp": message.content().replace('\n', "\n\n"),
+        });
+        let res = self
+            .client
+            .post(url)
+            .form(&form)
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+        if res["code"].as_i64() != Some(0) {
+            return Err(anyhow!("Server酱通知发送失败:{}", res));
+        }
+        info!("Server酱通知发送成功...");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[tokio::test]
+    async fn notify_payload() {
+        let (url, request) = testing::serve_once(r#"{"code":0}"#);
+        let mut notifier = ServerChanNotifier::new("SCTkey").unwrap();
+        notifier.api_base = url;
+        let message = NotifyMessage::new("测试账号", "演唱会", "2024-06-15 周六 19:30", "看台 680元");
+
+        notifier.notify(&message).await.unwrap();
+
+        let request = request.recv().unwrap();
+        assert!(request.starts_with("POST /SCTkey.send "));
+        let body = request.split("\r\n\r\n").nth(1).unwrap().replace('+', " ");
+        let body = urlencoding::decode(&body).unwrap();
+        assert!(body.contains("title=[测试账号]大麦抢票成功"));
+        // markdown换行
+        assert!(body.contains("账号备注: 测试账号\n\n门票名称: 演唱会"));
+    }
+
+    #[tokio::test]
+    async fn notify_failed() {
+        let (url, _request) = testing::serve_once(r#"{"code":40001,"message":"bad pushkey"}"#);
+        let mut notifier = ServerChanNotifier::new("SCTkey").unwrap();
+        notifier.api_base = url;
+
+        let res = notifier.notify(&NotifyMessage::error("测试账号", "cookie已过期")).await;
+
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use log::info;
+use reqwest::Client;
+use serde_json::json;
+use std::time::Duration;
+
+use super::{Notifier, NotifyMessage};
+
+// 通知请求超时时间
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Telegram机器人通知
+#[derive(Debug)]
+pub struct TelegramNotifier {
+    pub client: Client,
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+impl TelegramNotifier {
+    pub fn new(bot_token: &str, chat_id: &str) -> Result<Self> {
+        let client = reqwest::Client::builder().timeout(TIMEOUT).build()?;
+        Ok(Self {
+            client,
+            bot_token: bot_token.to_string(),
+            chat_id: chat_id.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &str {
+        "Telegram"
+    }
+
+    async fn notify(&self, message: &NotifyMessage) -> Result<()> {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token);
+        let data = json!({
+            "chat_id": self.chat_id,
+            "text": format!("{}\n\n{}", message.title(), message.content()),
+        });
+        self.client
+            .post(url)
+            .json(&data)
+            .send()
+            .await?
+            .error_for_status()?;
+        info!("Telegram通知发送成功...");
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use log::info;
+use reqwest::Client;
+use std::time::Duration;
+
+use super::{Notifier, NotifyMessage};
+
+// 通知请求超时时间
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Webhook通知, 将通知内容以JSON格式POST到指定地址
+#[derive(Debug)]
+pub struct WebhookNotifier {
+    pub client: Client,
+    pub url: String,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: &str) -> Result<Self> {
+        let client = reqwest::Client::builder().timeout(TIMEOUT).build()?;
+        Ok(Self {
+            client,
+            url: url.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        "Webhook"
+    }
+
+    async fn notify(&self, message: &NotifyMessage) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .json(message)
+            .send()
+            .await?
+            .error_for_status()?;
+        info!("Webhook通知发送成功...");
+        Ok(())
+    }
+}
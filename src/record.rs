@@ -0,0 +1,71 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::Path,
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+// 多个账号同时写入时串行化
+static RECORD_LOCK: Mutex<()> = Mutex::new(());
+
+// 下单记录
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OrderRecord {
+    // 账号备注
+    pub remark: String,
+
+    // 门票名称
+    pub ticket_name: String,
+
+    // 场次名称
+    pub perform_name: String,
+
+    // 票档名称
+    pub sku_name: String,
+
+    // 订单号
+    pub order_id: Option<String>,
+
+    // 支付链接
+    pub pay_url: Option<String>,
+
+    // 下单时间
+    pub order_time: String,
+}
+
+impl OrderRecord {
+    pub fn new(
+        remark: &str,
+        ticket_name: &str,
+        perform_name: &str,
+        sku_name: &str,
+        order_id: Option<String>,
+        pay_url: Option<String>,
+    ) -> Self {
+        let local: DateTime<Local> = Local::now();
+        Self {
+            remark: remark.to_string(),
+            ticket_name: ticket_name.to_string(),
+            perform_name: perform_name.to_string(),
+            sku_name: sku_name.to_string(),
+            order_id,
+            pay_url,
+            order_time: local.format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+
+    // 以JSON Lines格式追加到文件
+    pub fn append_to(&self, path: &Path) -> Result<()> {
+        let mut line = serde_json::to_string(self)?;
+        line.push('\n');
+
+        let _guard = RECORD_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
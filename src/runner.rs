@@ -0,0 +1,116 @@
+use crate::{
+    clients::dm::build_rate_limiter,
+    config::{Account, Config},
+    events::EventSink,
+    i18n::{tr, Msg},
+    logger::ACCOUNT_REMARK,
+    ticket::DmTicket,
+};
+use futures::future::join_all;
+use governor::DefaultDirectRateLimiter;
+use log::{error, info};
+use std::{collections::HashMap, fmt, sync::Arc};
+use tokio_util::sync::CancellationToken;
+
+// 单个账号的运行结果
+#[derive(Debug)]
+pub struct AccountOutcome {
+    // 账号备注
+    pub remark: String,
+
+    // 是否抢购成功, 失败时为错误原因
+    pub result: Result<bool, String>,
+
+    // 是否因其他账号抢购成功而停止
+    pub stopped: bool,
+}
+
+impl fmt::Display for AccountOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match &self.result {
+            Ok(true) => tr(Msg::OutcomeSuccess, &[&self.remark]),
+            Ok(false) if self.stopped => tr(Msg::OutcomeStopped, &[&self.remark]),
+            Ok(false) => tr(Msg::OutcomeNotGot, &[&self.remark]),
+            Err(e) => tr(Msg::OutcomeFailed, &[&self.remark, e]),
+        };
+        write!(f, "{}", text)
+    }
+}
+
+// 运行单个账号, 返回运行结果及是否被其他账号停止
+async fn run_account(
+    account: Account,
+    rate_limiter: Option<Arc<DefaultDirectRateLimiter>>,
+    cancel_token: CancellationToken,
+    stop_on_success: bool,
+    event_sink: Arc<dyn EventSink>,
+) -> (Result<bool, String>, bool) {
+    let remark = account.remark.clone();
+    let mut dm_ticket = match DmTicket::new(account).await {
+        Ok(dm_ticket) => dm_ticket,
+        Err(e) => return (Err(format!("初始化失败, {:?}", e)), false),
+    };
+    dm_ticket.client.rate_limiter = rate_limiter;
+    dm_ticket.cancel_token = cancel_token.clone();
+    dm_ticket.event_sink = event_sink;
+
+    let result = dm_ticket.run().await.map_err(|e| format!("{:?}", e));
+    let stopped = cancel_token.is_cancelled();
+    if stop_on_success && result == Ok(true) && !stopped {
+        info!("账号:{}, 抢购成功, 正在停止其他账号...", remark);
+        cancel_token.cancel();
+    }
+    (result, stopped)
+}
+
+// 并发运行多个账号, 单个账号出错不影响其他账号, event_sink接收各账号的状态事件
+pub async fn run_accounts(config: Config, event_sink: Arc<dyn EventSink>) -> Vec<AccountOutcome> {
+    let rate_limiter = build_rate_limiter(config.max_requests_per_second);
+    let cancel_token = CancellationToken::new();
+    // 同一账号多个门票共享的停止信号
+    let mut group_tokens: HashMap<String, CancellationToken> = HashMap::new();
+    let mut remarks = Vec::new();
+    let mut handlers = Vec::new();
+
+    for account in config.accounts {
+        let remark = account.remark.clone();
+        remarks.push(remark.clone());
+        // 未开启stop_on_success/stop_other_tickets时各账号使用独立的停止信号
+        let stop_on_success = config.stop_on_success || account.stop_other_tickets;
+        let token = match (config.stop_on_success, account.stop_other_tickets) {
+            (true, _) => cancel_token.clone(),
+            (false, true) => group_tokens.entry(account.group.clone()).or_default().clone(),
+            (false, false) => CancellationToken::new(),
+        };
+        handlers.push(tokio::spawn(ACCOUNT_REMARK.scope(
+            remark,
+            run_account(
+                account,
+                rate_limiter.clone(),
+                token,
+                stop_on_success,
+                event_sink.clone(),
+            ),
+        )));
+    }
+
+    join_all(handlers)
+        .await
+        .into_iter()
+        .zip(remarks)
+        .map(|(res, remark)| {
+            let (result, stopped) = match res {
+                Ok(res) => res,
+                Err(e) => (Err(format!("任务异常退出, {}", e)), false),
+            };
+            if let Err(e) = &result {
+                error!("账号:{}, 运行失败, 原因:{}", remark, e);
+            }
+            AccountOutcome {
+                remark,
+                result,
+                stopped,
+            }
+        })
+        .collect()
+}
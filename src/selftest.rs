@@ -0,0 +1,115 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::{config::Account, ticket::DmTicket};
+
+// 单项检查结果
+#[derive(Debug)]
+pub struct CheckResult {
+    // 检查项名称
+    pub name: &'static str,
+
+    // 是否通过
+    pub passed: bool,
+
+    // 检查结果说明, 通过时为获取到的信息, 失败时为错误原因
+    pub detail: String,
+
+    // 耗时
+    pub elapsed: Duration,
+}
+
+impl CheckResult {
+    fn new<T, E: fmt::Debug>(
+        name: &'static str,
+        result: &Result<T, E>,
+        detail: impl FnOnce(&T) -> String,
+        elapsed: Duration,
+    ) -> Self {
+        let (passed, detail) = match result {
+            Ok(value) => (true, detail(value)),
+            Err(e) => (false, format!("{:?}", e)),
+        };
+        Self {
+            name,
+            passed,
+            detail,
+            elapsed,
+        }
+    }
+}
+
+impl fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}, 耗时:{}毫秒, {}",
+            match self.passed {
+                true => "通过",
+                false => "失败",
+            },
+            self.name,
+            self.elapsed.as_millis(),
+            self.detail
+        )
+    }
+}
+
+// 开抢前自检: 获取token/连接代理、检查cookie、获取门票信息, 不进入抢购流程
+pub async fn run_self_test(account: Account) -> Vec<CheckResult> {
+    let mut checks = Vec::new();
+
+    let start = Instant::now();
+    let dm_ticket = DmTicket::new(account).await;
+    checks.push(CheckResult::new(
+        "获取token/连接代理",
+        &dm_ticket,
+        |_| "token获取成功".to_string(),
+        start.elapsed(),
+    ));
+    let dm_ticket = match dm_ticket {
+        Ok(dm_ticket) => dm_ticket,
+        Err(_) => return checks,
+    };
+
+    let start = Instant::now();
+    let user_info = dm_ticket.get_user_info().await;
+    checks.push(CheckResult::new(
+        "检查cookie",
+        &user_info,
+        |info| format!("账号昵称:{}", info.nickname),
+        start.elapsed(),
+    ));
+
+    let start = Instant::now();
+    let ticket_info = dm_ticket.get_ticket_info(dm_ticket.account.ticket.id.clone()).await;
+    checks.push(CheckResult::new(
+        "获取门票信息",
+        &ticket_info,
+        |info| {
+            format!(
+                "门票名称:{}",
+                info.detail_view_component_map.item.static_data.item_base.item_name
+            )
+        },
+        start.elapsed(),
+    ));
+
+    if let Some(offset) = dm_ticket.client.server_time_offset() {
+        checks.push(CheckResult {
+            name: "服务器时间",
+            passed: true,
+            detail: format!("本地时间与服务器时间偏差:{}毫秒", offset),
+            elapsed: Duration::ZERO,
+        });
+    }
+
+    checks
+}
+
+// 所有检查项是否通过
+pub fn all_passed(checks: &[CheckResult]) -> bool {
+    checks.iter().all(|check| check.passed)
+}
@@ -0,0 +1,80 @@
+use std::{fmt, time::Duration};
+
+// 带耗时的接口结果
+#[derive(Debug, Clone)]
+pub struct Timed<T> {
+    pub value: T,
+    pub elapsed: Duration,
+}
+
+impl<T> Timed<T> {
+    pub fn new(value: T, elapsed: Duration) -> Self {
+        Self { value, elapsed }
+    }
+}
+
+// 单个接口的耗时统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LatencyStats {
+    pub count: u32,
+    pub min: Option<Duration>,
+    pub max: Option<Duration>,
+    pub total: Duration,
+}
+
+impl LatencyStats {
+    // 记录一次耗时
+    pub fn record(&mut self, elapsed: Duration) {
+        self.count += 1;
+        self.total += elapsed;
+        self.min = Some(self.min.map_or(elapsed, |min| min.min(elapsed)));
+        self.max = Some(self.max.map_or(elapsed, |max| max.max(elapsed)));
+    }
+
+    // 平均耗时
+    pub fn avg(&self) -> Option<Duration> {
+        match self.count {
+            0 => None,
+            count => Some(self.total / count),
+        }
+    }
+}
+
+impl fmt::Display for LatencyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min, self.max, self.avg()) {
+            (Some(min), Some(max), Some(avg)) => write!(
+                f,
+                "次数:{}, 最小:{:?}, 最大:{:?}, 平均:{:?}",
+                self.count, min, max, avg
+            ),
+            _ => write!(f, "无"),
+        }
+    }
+}
+
+// 抢购耗时统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunStats {
+    // 抢购次数
+    pub attempts: u32,
+
+    // 生成订单耗时
+    pub build_order: LatencyStats,
+
+    // 提交订单耗时
+    pub submit_order: LatencyStats,
+
+    // 总耗时
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for RunStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "抢购统计:\n\t抢购次数: {}\n\t生成订单耗时: {}\n\t提交订单耗时: {}\n\t总耗时: {:?}",
+            self.attempts, self.build_order, self.submit_order, self.elapsed
+        )
+    }
+}
@@ -0,0 +1,314 @@
+// 测试辅助: 模拟传输层/HTTP服务器、测试账号及接口返回数据
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{Read, Write},
+    net::TcpListener,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::{
+    clients::{
+        dm::DmClient,
+        token::TokenClient,
+        transport::{HttpTransport, TransportResponse},
+    },
+    config::Account,
+    dump::api_name,
+    models::{
+        order::OrderInfo,
+        perform::{Perform, PerformInfo, Sku},
+        ticket::PerformBase,
+    },
+    ticket::DmTicket,
+};
+
+pub const BUILD_ORDER_API: &str = "mtop.trade.order.build.h5";
+pub const SUBMIT_ORDER_API: &str = "mtop.trade.order.create.h5";
+
+pub const SUCCESS_RET: &str = "SUCCESS::调用成功";
+pub const SOLD_OUT_RET: &str = "B-00203-200-034::您选购的商品信息已过期，请重新查询";
+pub const SESSION_EXPIRED_RET: &str = "FAIL_SYS_SESSION_EXPIRED::Session过期";
+
+// 模拟传输层, 按API依次返回预设的响应, 并记录请求过的API
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    // 预设的响应, Err为网络错误
+    responses: Mutex<HashMap<String, VecDeque<Result<String, String>>>>,
+    // 按请求顺序记录的API名称及表单数据
+    calls: Mutex<Vec<(String, Value)>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    // 预设API的返回内容
+    pub fn push(&self, api: &str, body: Value) {
+        self.responses
+            .lock()
+            .unwrap()
+            .entry(api.to_string())
+            .or_default()
+            .push_back(Ok(body.to_string()));
+    }
+
+    // 预设API的网络错误, 如: 请求超时
+    pub fn push_error(&self, api: &str, reason: &str) {
+        self.responses
+            .lock()
+            .unwrap()
+            .entry(api.to_string())
+            .or_default()
+            .push_back(Err(reason.to_string()));
+    }
+
+    // API被请求的次数
+    pub fn calls(&self, api: &str) -> usize {
+        self.calls.lock().unwrap().iter().filter(|(call, _)| call == api).count()
+    }
+
+    // API最近一次请求的表单数据
+    pub fn last_form(&self, api: &str) -> Option<Value> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|(call, _)| call == api)
+            .map(|(_, form)| form.clone())
+    }
+}
+
+#[async_trait]
+impl HttpTransport for Arc<MockTransport> {
+    async fn post(
+        &self,
+        url: &str,
+        _cookie: &str,
+        _query: &Value,
+        form: &Value,
+    ) -> Result<TransportResponse> {
+        let api = api_name(url).to_string();
+        self.calls.lock().unwrap().push((api.clone(), form.clone()));
+        let response = self
+            .responses
+            .lock()
+            .unwrap()
+            .get_mut(&api)
+            .and_then(|queue| queue.pop_front());
+        match response {
+            Some(Ok(body)) => Ok(TransportResponse {
+                status: 200,
+                retry_after: None,
+                date: None,
+                body,
+            }),
+            Some(Err(reason)) => Err(anyhow!(reason)),
+            None => Err(anyhow!("模拟数据中没有API:{}的响应", api)),
+        }
+    }
+}
+
+// 接口返回数据
+pub fn dm_res(ret: &str, data: Value) -> Value {
+    json!({
+        "api": "mock",
+        "data": data,
+        "ret": [ret],
+        "v": "1.0",
+    })
+}
+
+// 接口调用成功的返回数据
+pub fn success(data: Value) -> Value {
+    dm_res(SUCCESS_RET, data)
+}
+
+// 生成订单返回的订单数据, 包含viewers位实名观演人
+pub fn order_info_data(viewers: usize) -> Value {
+    let viewer_list = (0..viewers)
+        .map(|i| json!({"viewerName": format!("观演人{}", i + 1), "isUsed": false}))
+        .collect::<Vec<_>>();
+    json!({
+        "data": {
+            "dmViewer_1": {"fields": {"viewerList": viewer_list}},
+            "confirmOrder_1": {"fields": {}},
+            "order_1": {"fields": {}},
+            "realPay_1": {"fields": {"price": "680.00"}},
+        },
+        "global": {"secretKey": "submitref", "secretValue": "secret"},
+        "hierarchy": {
+            "component": ["confirmOrder_1", "order_1", "dmViewer_1"],
+            "root": "confirmOrder_1",
+            "baseType": [],
+            "structure": {"confirmOrder_1": ["order_1", "dmViewer_1"]},
+        },
+        "linkage": {
+            "input": ["dmViewer_1"],
+            "request": [],
+            "signature": "signature",
+            "common": {
+                "queryParams": "",
+                "compress": true,
+                "validateParams": "",
+                "structures": "",
+                "submitParams": "",
+            },
+        },
+    })
+}
+
+// 包含viewers位实名观演人的订单
+pub fn order_info(viewers: usize) -> OrderInfo {
+    serde_json::from_value(order_info_data(viewers)).unwrap()
+}
+
+// 订单中已选择的实名观演人序号(从1开始)
+pub fn used_viewers(order_data: &Value) -> Vec<usize> {
+    order_data["dmViewer_1"]["fields"]["viewerList"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter(|(_, viewer)| viewer["isUsed"].as_bool() == Some(true))
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+// 提交订单成功返回的数据
+pub fn submit_data(order_id: &str) -> Value {
+    json!({
+        "bizOrderId": order_id,
+        "alipayWapCashierUrl": format!("https://pay.example.com/{}", order_id),
+    })
+}
+
+// 测试账号, 重试不等待, 网络错误不重试
+pub fn account() -> Account {
+    serde_json::from_value(json!({
+        "remark": "测试账号",
+        "cookie": "cookie2=test",
+        "ticket": {
+            "id": "123456",
+            "num": 1,
+            "sessions": 1,
+            "grade": 1,
+            "pick_up_leaks": {"enabled": false, "times": 1},
+        },
+        "retry_times": 2,
+        "retry_interval": 0,
+        "retry_strategy": "fixed",
+        "wait_for_submit_time": 0,
+        "transport_retry_times": 0,
+    }))
+    .unwrap()
+}
+
+// 票档
+pub fn sku(sku_id: &str, price_name: &str) -> Sku {
+    serde_json::from_value(json!({
+        "skuId": sku_id,
+        "itemId": "123456",
+        "priceName": price_name,
+        "skuSalable": "true",
+    }))
+    .unwrap()
+}
+
+// 场次, perform_ids为空时没有可直接购买的演出(如选座类演出)
+pub fn perform_base(name: &str, perform_ids: &[&str]) -> PerformBase {
+    let performs: Vec<Value> = perform_ids
+        .iter()
+        .map(|id| json!({ "performId": id, "itemId": "123456", "performName": id }))
+        .collect();
+    serde_json::from_value(json!({
+        "name": name,
+        "timeSpan": "",
+        "performBaseTagDesc": "",
+        "performs": performs,
+    }))
+    .unwrap()
+}
+
+// 场次/票档信息
+pub fn perform_info(perform_id: &str, sku_list: Vec<Sku>) -> PerformInfo {
+    let mut perform_info = PerformInfo {
+        perform: Perform {
+            perform_id: perform_id.to_string(),
+            perform_name: perform_id.to_string(),
+            sku_list,
+        },
+    };
+    perform_info.fill_perform_name();
+    perform_info
+}
+
+// 使用模拟传输层的抢购实例
+pub async fn dm_ticket(account: Account, transport: &Arc<MockTransport>) -> DmTicket {
+    let client = DmClient::with_transport(
+        String::new(),
+        &account,
+        Box::new(transport.clone()),
+        TokenClient::offline().unwrap(),
+    )
+    .unwrap();
+    DmTicket::with_client(account, client).await.unwrap()
+}
+
+// 模拟HTTP服务器, 接收一个请求并返回JSON响应, 返回服务器地址及收到的请求(请求头及请求体)
+pub fn serve_once(response: &str) -> (String, mpsc::Receiver<String>) {
+    serve_once_with_headers("", response)
+}
+
+// 同serve_once, headers为额外的响应头, 每行以\r\n结尾
+pub fn serve_once_with_headers(headers: &str, response: &str) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let response = response.to_string();
+    let extra_headers = headers.to_string();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        // 读取请求头, 再按Content-Length读取请求体
+        let body_start = loop {
+            let size = stream.read(&mut buf).unwrap();
+            request.extend_from_slice(&buf[..size]);
+            if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            if size == 0 {
+                break request.len();
+            }
+        };
+        let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+        let content_length = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        while request.len() < body_start + content_length {
+            let size = stream.read(&mut buf).unwrap();
+            if size == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..size]);
+        }
+        let reply = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            extra_headers,
+            response.len(),
+            response
+        );
+        stream.write_all(reply.as_bytes()).unwrap();
+        let _ = sender.send(String::from_utf8_lossy(&request).to_string());
+    });
+    (url, receiver)
+}
@@ -1,90 +1,439 @@
 use std::{
-    io::{self, Write},
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
     time::{Duration, Instant},
-    process::Command,
-    env,
 };
 
 use crate::{
+    cache::TtlCache,
     clients::dm::DmClient,
-    config::Account,
-    error::DmApiError,
+    config::{parse_ticket_id, Account, SpinMode},
+    error::{DmApiError, DmTicketError},
+    events::{EventSink, LogSink},
+    i18n::{tr, Msg},
+    logger,
     models::{
-        order::{OrderForm, OrderInfo, OrderParams, SubmitOrderParams},
-        perform::{PerformForm, PerformInfo, PerformParams},
-        ticket::{TicketInfo, TicketInfoForm, TicketInfoParams},
+        order::{OrderForm, OrderInfo, OrderParams, SubmitOrderParams, SubmitOrderResult},
+        perform::{PerformForm, PerformInfo, PerformParams, SaleState, Sku},
+        ticket::{PerformBase, TicketInfo, TicketInfoForm, TicketInfoParams},
         user::{GetUserInfoForm, GetUserInfoParams, UserInfoData},
-        DmRes,
+        DataField,
     },
+    notify::{build_notifiers, notify_all, Notifier, NotifyMessage},
+    record::OrderRecord,
+    stats::{RunStats, Timed},
+    time::TimeSync,
 };
 use anyhow::{anyhow, Result};
-use chrono::{DateTime, Local};
-use log::{debug, error, info};
-use serde_json::json;
-use tokio::signal;
+use chrono::Local;
+use futures::stream::{FuturesUnordered, StreamExt};
+use log::{debug, error, info, warn};
+use rand::{thread_rng, Rng};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::{signal, sync::Semaphore};
+use tokio_util::sync::CancellationToken;
+
+// 门票概览, 列出所有场次/票档
+#[derive(Serialize, Debug)]
+pub struct TicketOverview {
+    pub ticket_id: String,
+    pub ticket_name: String,
+    pub sell_start_time: String,
+    pub sessions: Vec<SessionOverview>,
+}
+
+// 场次概览
+#[derive(Serialize, Debug)]
+pub struct SessionOverview {
+    // 场次序号, 对应ticket.sessions
+    pub index: usize,
+    pub name: String,
+    pub perform_id: String,
+    pub grades: Vec<GradeOverview>,
+}
+
+// 票档概览
+#[derive(Serialize, Debug)]
+pub struct GradeOverview {
+    // 票档序号, 对应ticket.grade
+    pub index: usize,
+    pub sku_id: String,
+    pub price_name: String,
+    pub price: Option<f64>,
+    pub salable: bool,
+}
+
+impl fmt::Display for TicketOverview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", tr(Msg::OverviewTicketName, &[&self.ticket_name]))?;
+        writeln!(f, "{}", tr(Msg::OverviewSellStart, &[&self.sell_start_time]))?;
+        for session in self.sessions.iter() {
+            writeln!(f, "\n{}", tr(Msg::OverviewSession, &[&session.index, &session.name]))?;
+            if session.perform_id.is_empty() {
+                writeln!(f, "\t{}", tr(Msg::SessionNoPerformHint, &[]))?;
+                continue;
+            }
+            writeln!(
+                f,
+                "\t{:<6}{:<24}{:<12}{}",
+                tr(Msg::OverviewIndex, &[]),
+                tr(Msg::OverviewGradeName, &[]),
+                tr(Msg::OverviewPrice, &[]),
+                tr(Msg::OverviewSalable, &[])
+            )?;
+            for grade in session.grades.iter() {
+                writeln!(
+                    f,
+                    "\t{:<8}{:<24}{:<14}{}",
+                    grade.index,
+                    grade.price_name,
+                    grade
+                        .price
+                        .map(|price| format!("{:.2}", price))
+                        .unwrap_or_default(),
+                    tr(if grade.salable { Msg::Yes } else { Msg::No }, &[])
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// 抢购成功的场次/票档, 票档降级/捡漏时可能不是首选的票档
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct PurchasedTarget {
+    pub perform_name: String,
+    pub sku_id: String,
+    pub sku_name: String,
+    pub price: String,
+}
 
+impl PurchasedTarget {
+    pub fn new(perform_name: &str, sku: &Sku) -> Self {
+        Self {
+            perform_name: perform_name.to_string(),
+            sku_id: sku.sku_id.clone(),
+            sku_name: sku.price_name.clone(),
+            price: sku.price_text(),
+        }
+    }
+}
+
+// 跨票档凑票时单个订单的购买结果
+#[derive(Serialize, Debug, Clone)]
+pub struct Allocation {
+    pub perform_name: String,
+    pub sku_name: String,
+    pub num: usize,
+    pub order_id: Option<String>,
+    pub total_price: Option<String>,
+}
+
+impl fmt::Display for Allocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let order_id = self.order_id.clone().unwrap_or_else(|| tr(Msg::Unknown, &[]));
+        write!(
+            f,
+            "{}",
+            tr(Msg::Allocation, &[&self.perform_name, &self.sku_name, &self.num, &order_id])
+        )
+    }
+}
 
-const SUCCESS_FLAG: &str = "SUCCESS::调用成功";
-const RETRY_INTERVAL_MS: u64 = 100; // 定义常量，表示重试间隔时间（以毫秒为单位）
+// 自动校准early_submit_time的采样次数
+const CALIBRATION_SAMPLES: usize = 5;
+
+// 本地时间与服务器时间偏差超过该值时提示校时, 单位毫秒
+const CLOCK_SKEW_WARN_MS: i64 = 1000;
 
 pub struct DmTicket {
     pub client: DmClient,
     pub account: Account,
+    pub time_sync: TimeSync,
+    pub notifiers: Vec<Box<dyn Notifier>>,
+    pub stats: Mutex<RunStats>,
+    // 已提交订单成功, 之后的所有抢购直接返回, 防止重复下单
+    pub purchased: AtomicBool,
+    // 本次运行是否已记录时间偏差, 每次运行(run_once)开始时重置
+    pub clock_skew_logged: AtomicBool,
+    // 提交订单锁, 并发抢购时保证只有一个订单提交成功
+    pub submit_lock: futures::lock::Mutex<()>,
+    // 生成订单并发许可, 同时进行中的生成订单请求数不超过max_inflight
+    pub inflight_builds: Semaphore,
+    // 当前订单使用的实名观演人范围(已使用数量, 本次数量), 跨票档凑票时每个订单使用不同的观演人
+    pub viewer_range: Mutex<Option<(usize, usize)>>,
+    // 提交订单成功的结果
+    pub order_result: Mutex<Option<SubmitOrderResult>>,
+    // 提交订单成功的场次/票档, 与order_result同时记录
+    pub purchased_target: Mutex<Option<PurchasedTarget>>,
+    // 门票限购数量, 从门票信息中读取
+    pub purchase_limit: Option<usize>,
+    // 开售时间戳(毫秒), 从门票信息中读取, 用于区分未开售与已售罄
+    pub sell_start_timestamp: Option<i64>,
+    // 停止信号, 多账号共享, 任一账号抢购成功后停止其他账号
+    pub cancel_token: CancellationToken,
+    // 是否监听CTRL-C停止抢购, 嵌入其他程序时可关闭, 改用cancel_token停止
+    pub listen_ctrl_c: bool,
+    // 状态事件(倒计时/抢购尝试/查询库存/抢购成功等), 默认输出到日志
+    pub event_sink: Arc<dyn EventSink>,
+    // 运行截止时间, 由max_runtime_seconds在run开始时设置
+    pub deadline: Option<tokio::time::Instant>,
+    // 门票信息缓存, 按门票ID缓存
+    pub ticket_info_cache: TtlCache<TicketInfo>,
+    // 场次/票档信息缓存, 按场次ID缓存
+    pub perform_info_cache: TtlCache<PerformInfo>,
+}
+
+// 解析Api返回的错误类型
+fn api_error(e: &anyhow::Error) -> Option<&DmApiError> {
+    e.downcast_ref::<DmApiError>()
+}
+
+// 按日期匹配场次, 返回场次序号(从1开始)。匹配多个时选择最早的场次
+fn find_session_by_date(perform_bases: &[PerformBase], date: &str) -> Result<usize> {
+    perform_bases
+        .iter()
+        .enumerate()
+        .filter(|(_, base)| {
+            base.name.contains(date)
+                || base.performs.iter().any(|p| p.perform_name.contains(date))
+        })
+        .min_by(|(_, a), (_, b)| a.name.cmp(&b.name))
+        .map(|(idx, _)| idx + 1)
+        .ok_or_else(|| {
+            let dates = perform_bases
+                .iter()
+                .map(|base| base.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            anyhow!(
+                "配置的场次日期(ticket.session_date) {} 未匹配到场次, 可选场次: {}",
+                date,
+                dates
+            )
+        })
+}
+
+// 按价格上限选择票档, 返回不超过max_price的最高价票档
+fn find_sku_by_max_price(sku_list: &[Sku], max_price: f64) -> Result<&Sku> {
+    sku_list
+        .iter()
+        .filter_map(|sku| sku.price_value().map(|price| (price, sku)))
+        .filter(|(price, _)| *price <= max_price)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, sku)| sku)
+        .ok_or_else(|| {
+            let prices = sku_list
+                .iter()
+                .map(|sku| format!("{}({})", sku.price_name, sku.price_text()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            anyhow!(
+                "没有不超过价格上限(ticket.max_price) {} 的票档, 可选票档: {}",
+                max_price,
+                prices
+            )
+        })
+}
+
+// 随机抖动等待时间, 返回interval ± 0~jitter_ms
+fn with_jitter(interval: u64, jitter_ms: u64) -> u64 {
+    if jitter_ms == 0 {
+        return interval;
+    }
+    let offset = thread_rng().gen_range(0..=jitter_ms);
+    match thread_rng().gen_bool(0.5) {
+        true => interval.saturating_add(offset),
+        false => interval.saturating_sub(offset),
+    }
+}
+
+// 校验购买数量, 为0时返回错误, 超出限购数量时按限购数量购买
+fn effective_buy_num(buy_num: usize, purchase_limit: Option<usize>) -> Result<usize> {
+    if buy_num == 0 {
+        return Err(anyhow!(tr(Msg::BuyNumZero, &[])));
+    }
+    match purchase_limit {
+        Some(limit) if limit > 0 && buy_num > limit => {
+            warn!("{}", tr(Msg::BuyNumCapped, &[&buy_num, &limit]));
+            Ok(limit)
+        }
+        _ => Ok(buy_num),
+    }
+}
+
+// 捡漏时尝试购买的票档下标(从0开始), 按grades配置的优先级顺序, 未配置时按票档顺序
+fn leak_grade_order(grades: &[usize], sku_count: usize) -> Vec<usize> {
+    match grades.is_empty() {
+        true => (0..sku_count).collect(),
+        false => grades
+            .iter()
+            .filter_map(|grade| grade.checked_sub(1))
+            .filter(|idx| *idx < sku_count)
+            .collect(),
+    }
+}
+
+// 是否为用户取消(CTRL-C)
+fn is_cancelled(e: &anyhow::Error) -> bool {
+    matches!(e.downcast_ref::<DmTicketError>(), Some(DmTicketError::Cancelled))
+}
+
+// 是否为提交订单结果未知, 此时不能再提交任何订单
+fn is_submit_uncertain(e: &anyhow::Error) -> bool {
+    matches!(e.downcast_ref::<DmTicketError>(), Some(DmTicketError::SubmitUncertain(_)))
+}
+
+// 是否为实名观演人配置错误
+fn is_invalid_viewers(e: &anyhow::Error) -> bool {
+    matches!(e.downcast_ref::<DmTicketError>(), Some(DmTicketError::InvalidViewers(_)))
+}
+
+// 是否需要停止抢购: 订单结果未知时不能再提交任何订单, 实名观演人配置错误时重试无意义
+fn should_stop(e: &anyhow::Error) -> bool {
+    is_submit_uncertain(e) || is_invalid_viewers(e)
+}
+
+// 按viewer_count选择的实名观演人序号(从1开始), 配置了real_names时选择其中前viewer_count位, 否则选择前viewer_count位
+fn viewer_count_indices(viewer_count: usize, real_names: &[usize], len: usize) -> Result<Vec<usize>> {
+    let indices: Vec<usize> = match real_names.is_empty() {
+        true => (1..=viewer_count).collect(),
+        false => real_names.iter().take(viewer_count).copied().collect(),
+    };
+    if viewer_count == 0
+        || indices.len() < viewer_count
+        || indices.iter().any(|idx| *idx == 0 || *idx > len)
+    {
+        return Err(anyhow!(DmTicketError::InvalidViewers(tr(
+            Msg::ViewerCountOutOfRange,
+            &[&viewer_count, &len]
+        ))));
+    }
+    Ok(indices)
+}
+
+// 并发抢购流程的错误优先级, 数值越大越优先: 取消/订单状态未知 > 商品信息过期 > 其他Api错误 > 其他错误
+fn error_priority(e: &anyhow::Error) -> u8 {
+    if e.downcast_ref::<DmTicketError>().is_some() {
+        return 3;
+    }
+    match api_error(e) {
+        Some(DmApiError::ProductExpired) => 2,
+        Some(_) => 1,
+        None => 0,
+    }
+}
+
+// 清理cookie, 去掉空格及_m_h5_tk相关字段
+fn clean_cookie(cookie: &str) -> String {
+    cookie
+        .replace(' ', "")
+        .split(';')
+        .filter(|e| !e.starts_with("_m_h5_tk"))
+        .collect::<Vec<&str>>()
+        .join(";")
 }
 
 impl DmTicket {
     pub async fn new(account: Account) -> Result<Self> {
-        let cookie = account
-            .cookie
-            .clone()
-            .replace(' ', "")
-            .split(';')
-            .filter(|e| !e.starts_with("_m_h5_tk"))
-            .collect::<Vec<&str>>()
-            .join(";");
+        account.validate()?;
+        let cookie = clean_cookie(&account.load_cookie()?);
 
-        let client = DmClient::new(cookie).await?;
+        let client = DmClient::new(cookie, &account).await?;
 
-        Ok(Self { client, account })
+        Self::with_client(account, client).await
     }
 
-    // 获取用户信息
+    // 使用指定的请求客户端初始化, 用于回放/测试
+    pub async fn with_client(mut account: Account, client: DmClient) -> Result<Self> {
+        account.ticket.id = parse_ticket_id(&account.ticket.id)?;
+
+        let time_sync = match &account.ntp_server {
+            Some(server) => TimeSync::from_ntp(server).await.unwrap_or_else(|e| {
+                warn!("NTP时间校准失败, 使用本地时间, 原因:{:?}", e);
+                TimeSync::default()
+            }),
+            None => TimeSync::default(),
+        };
+
+        let notifiers = build_notifiers(&account.notify)?;
+        let cache_ttl = Duration::from_secs(account.info_cache_ttl_secs);
+        let max_inflight = account.max_inflight();
+
+        Ok(Self {
+            client,
+            account,
+            time_sync,
+            notifiers,
+            stats: Mutex::new(RunStats::default()),
+            purchased: AtomicBool::new(false),
+            clock_skew_logged: AtomicBool::new(false),
+            submit_lock: futures::lock::Mutex::new(()),
+            inflight_builds: Semaphore::new(max_inflight),
+            viewer_range: Mutex::new(None),
+            order_result: Mutex::new(None),
+            purchased_target: Mutex::new(None),
+            purchase_limit: None,
+            sell_start_timestamp: None,
+            cancel_token: CancellationToken::new(),
+            listen_ctrl_c: true,
+            event_sink: Arc::new(LogSink::default()),
+            deadline: None,
+            ticket_info_cache: TtlCache::new(cache_ttl),
+            perform_info_cache: TtlCache::new(cache_ttl),
+        })
+    }
+
+    // 刷新会话, 重新获取token并重建请求客户端
+    pub async fn refresh_session(&mut self) -> Result<()> {
+        if self.client.is_offline() {
+            return Err(anyhow!("[回放] 离线模式不刷新会话"));
+        }
+        let cookie = clean_cookie(&self.account.load_cookie()?);
+        let rate_limiter = self.client.rate_limiter.clone();
+        // 先保存当前会话的cookie, 新的请求客户端从文件加载最新的cookie
+        self.client.save_cookie_store();
+        self.client = DmClient::renew(cookie, &self.account).await?;
+        self.client.rate_limiter = rate_limiter;
+        info!("{}", tr(Msg::SessionRefreshed, &[]));
+        Ok(())
+    }
+
+    // 获取用户信息, 回放(离线)模式返回占位信息
     pub async fn get_user_info(&self) -> Result<UserInfoData> {
-        let url = "https://mtop.damai.cn/h5/mtop.damai.wireless.user.session.transform/1.0/";
+        if self.client.is_offline() {
+            return Ok(UserInfoData {
+                nickname: "[回放]".to_string(),
+                user_id: 0,
+            });
+        }
+        let url = self.client.endpoints.user_info();
         let params = GetUserInfoParams::build()?;
         let form = GetUserInfoForm::build()?;
-        let res = self.client.request(url, params, form, 0).await?;
-        if res.ret.contains(&SUCCESS_FLAG.to_string()) {
-            let user_info_data = serde_json::from_value(res.data)?;
-            Ok(user_info_data)
-        } else {
-            Err(anyhow!("{}", res.ret[0]))
-        }
+        self.client
+            .request_typed(&url, params, form, DataField::Data)
+            .await
     }
 
     // 获取门票信息
     pub async fn get_ticket_info(&self, ticket_id: String) -> Result<TicketInfo> {
-        let url = "https://mtop.damai.cn/h5/mtop.alibaba.damai.detail.getdetail/1.2";
+        let url = self.client.endpoints.ticket_info();
 
         let params = TicketInfoParams::build()?;
 
         let data = TicketInfoForm::build(ticket_id)?;
 
-        let res = self.client.request(url, params, data, 0).await?;
-
-        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
-            true => {
-                debug!("获取门票信息成功, {:?}", res);
-
-                let ticket_info: TicketInfo =
-                    serde_json::from_str(res.data["result"].clone().as_str().unwrap())?;
-                Ok(ticket_info)
-            }
-            false => {
-                error!("获取门票信息失败, 结果:{:?}", res.ret);
-                Err(anyhow!("获取门票信息失败..."))
-            }
-        }
+        self.client
+            .request_typed(&url, params, data, DataField::Result)
+            .await
+            .map_err(|e| {
+                error!("获取门票信息失败, {}", e);
+                e
+            })
     }
 
     // 生成订单
@@ -94,66 +443,76 @@ impl DmTicket {
         sku_id: &String,
         buy_num: usize,
         attempt: usize,
-    ) -> Result<OrderInfo> {
+    ) -> Result<Timed<OrderInfo>> {
         let start = Instant::now();
 
-        let url = "https://mtop.damai.cn/h5/mtop.trade.order.build.h5/4.0/?";
+        let url = self.client.endpoints.build_order();
 
         let params = OrderParams::build()?;
 
         let data = OrderForm::build(item_id, sku_id, buy_num)?;
 
-        let res = self.client.request(url, params, data, attempt).await?;
+        let res = self.client.request(&url, params, data, attempt).await?;
 
         debug!("生成订单结果:{:?}, 花费时间:{:?}", res, start.elapsed());
 
-        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
-            true => {
-                let order_info: OrderInfo = serde_json::from_value(res.data)?;
-                Ok(order_info)
-            }
-            false => Err(anyhow!("{:?}", res.ret)),
-        }
+        let order_info: OrderInfo = serde_json::from_value(res.data)?;
+        Ok(Timed::new(order_info, start.elapsed()))
     }
 
-    // 提交订单
-    pub async fn submit_order(&self, order_info: OrderInfo) -> Result<DmRes> {
-        let start = Instant::now();
-
-        let url = "https://mtop.damai.cn/h5/mtop.trade.order.create.h5/4.0/";
-
+    // 生成提交订单的数据, 包含实名观演人选择
+    pub fn build_order_data(&self, order_info: &OrderInfo) -> Result<Value> {
         // 添加提交订单需要的数据
         let mut order_data = json!({});
+        let viewer_range = *self.viewer_range.lock().unwrap();
 
         for key in order_info.linkage.input.iter() {
             if key.starts_with("dmViewer_") {
                 let mut item = order_info.data[key].clone();
                 let num = self.account.ticket.num;
+                let viewer_count = self.account.ticket.viewer_count;
 
-                let viewer_list = item["fields"]["viewerList"].clone();
+                let viewer_list = &mut item["fields"]["viewerList"];
 
                 // 需选择实名观演人
                 if viewer_list.is_array() && !viewer_list.as_array().unwrap().is_empty() {
-                    // 实名观演人比购票数量少
-                    // if viewer_list.as_array().unwrap().len() < num {
-                    //     warn!("实名观演人小于实际购票数量, 请先添加实名观演人!");
-                    //     num = viewer_list.as_array().unwrap().len();
-                    // }
-                    for i in 0..num {
-                        item["fields"]["viewerList"][i]["isUsed"] = true.into();
-                    // if self.account.ticket.real_names.is_empty() {
-                    //     info!("未配置实名观演人, 默认选择前{}位观演人...", self.account.ticket.num);
-                    //     for i in 0..num {
-                    //         item["fields"]["viewerList"][i]["isUsed"] = true.into();
-                    //     }
-                    // }else{
-                    //     for i in 0..item["fields"]["viewerList"].as_array().unwrap_or(&Vec::new()).len() {
-                    //         let idx = i + 1;
-                    //         if self.account.ticket.real_names.contains(&idx) {
-                    //             item["fields"]["viewerList"][i]["isUsed"] = true.into();
-                    //         }
-                    //     }
-                    // }
+                    let len = viewer_list.as_array().unwrap().len();
+                    if self.account.ticket.all_real_names {
+                        info!("选择全部{}位实名观演人...", len);
+                        for i in 0..len {
+                            viewer_list[i]["isUsed"] = true.into();
+                        }
+                    } else if let Some(viewer_count) = viewer_count {
+                        // 观演人数与购票数量不一致, 按viewer_count选择
+                        let indices =
+                            viewer_count_indices(viewer_count, &self.account.ticket.real_names, len)?;
+                        info!("{}", tr(Msg::ViewerCountSelected, &[&viewer_count]));
+                        for idx in indices {
+                            viewer_list[idx - 1]["isUsed"] = true.into();
+                        }
+                    } else if self.account.ticket.real_names.is_empty() {
+                        // 跨票档凑票时从已使用的观演人之后开始选择
+                        let (offset, num) = viewer_range.unwrap_or((0, num));
+                        // 实名观演人比购票数量少
+                        if len < offset + num {
+                            warn!("实名观演人小于实际购票数量, 请先添加实名观演人!");
+                        }
+                        let end = len.min(offset + num);
+                        info!("未配置实名观演人, 默认选择第{}至{}位观演人...", offset + 1, end);
+                        for i in offset.min(end)..end {
+                            viewer_list[i]["isUsed"] = true.into();
+                        }
+                    } else {
+                        let (offset, num) = viewer_range.unwrap_or((0, usize::MAX));
+                        for idx in self.account.ticket.real_names.iter().skip(offset).take(num) {
+                            if *idx == 0 || *idx > len {
+                                return Err(anyhow!(DmTicketError::InvalidViewers(format!(
+                                    "实名观演人序号:{}超出范围, 当前账号共有{}位实名观演人",
+                                    idx, len
+                                ))));
+                            }
+                            viewer_list[idx - 1]["isUsed"] = true.into();
+                        }
                     }
                 }
                 order_data[key] = item;
@@ -168,13 +527,27 @@ impl DmTicket {
 
         // 添加order_xxxxx
         let keys_list = order_info.hierarchy.structure[confirm_order_key].clone();
-        for k in keys_list.as_array().unwrap() {
-            let s = k.as_str().unwrap();
+        for s in keys_list.as_array().into_iter().flatten().filter_map(|k| k.as_str()) {
             if s.starts_with("order_") {
                 order_data[s] = order_info.data[s].clone();
             }
         }
 
+        Ok(order_data)
+    }
+
+    // 提交订单
+    pub async fn submit_order(
+        &self,
+        order_info: &OrderInfo,
+        attempt: usize,
+    ) -> Result<Timed<SubmitOrderResult>> {
+        let start = Instant::now();
+
+        let url = self.client.endpoints.submit_order();
+
+        let order_data = self.build_order_data(&order_info)?;
+
         let order_hierarchy = json!({
             "structure": order_info.hierarchy.structure
         });
@@ -188,13 +561,14 @@ impl DmTicket {
             "signature": order_info.linkage.signature,
         });
 
-        let submit_order_params = SubmitOrderParams::build(order_info.global.secret_value)?;
+        let submit_order_params = SubmitOrderParams::build(order_info.global.secret_value.clone())?;
 
+        let order_feature = &self.account.order_feature;
         let feature = json!({
-            "subChannel": "damai@damaih5_h5",
-            "returnUrl": "https://m.damai.cn/damai/pay-success/index.html?spm=a2o71.orderconfirm.bottom.dconfirm&sqm=dianying.h5.unknown.value",
-            "serviceVersion": "2.0.0",
-            "dataTags": "sqm:dianying.h5.unknown.value"
+            "subChannel": order_feature.sub_channel,
+            "returnUrl": order_feature.return_url,
+            "serviceVersion": order_feature.service_version,
+            "dataTags": order_feature.data_tags,
         });
         let params = json!({
             "data": serde_json::to_string(&order_data)?,
@@ -206,13 +580,136 @@ impl DmTicket {
             "feature": serde_json::to_string(&feature)?,
         });
 
+        // 非Api错误时请求可能已被服务器处理, 订单可能已创建
         let res = self
             .client
-            .request(url, submit_order_params, sumbit_order_data, 0)
-            .await?;
+            .request(&url, submit_order_params, sumbit_order_data, attempt)
+            .await
+            .map_err(|e| match api_error(&e) {
+                Some(_) => e,
+                None => anyhow!(DmTicketError::SubmitUncertain(e.to_string())),
+            })?;
+
+        let elapsed = start.elapsed();
+        debug!("提交订单结果:{:?}, 花费时间:{:?}", res, elapsed);
+        Ok(Timed::new(SubmitOrderResult::from_data(&res.data), elapsed))
+    }
 
-        debug!("提交订单结果:{:?}, 花费时间:{:?}", res, start.elapsed());
-        Ok(res)
+    // 获取门票信息, 缓存未过期时直接返回缓存
+    pub async fn get_ticket_info_cached(&self, ticket_id: String) -> Result<TicketInfo> {
+        if let Some(ticket_info) = self.ticket_info_cache.get(&ticket_id) {
+            debug!("使用缓存的门票信息, 门票ID:{}", ticket_id);
+            return Ok(ticket_info);
+        }
+        let ticket_info = self.get_ticket_info(ticket_id.clone()).await?;
+        self.ticket_info_cache.insert(&ticket_id, ticket_info.clone());
+        Ok(ticket_info)
+    }
+
+    // 按场次优先级选择要购买的票档, 返回票档及其所属场次ID/名称
+    // 超出范围/没有可购买演出/获取信息失败/没有符合条件票档的场次跳过, 所有场次都不可用时返回最后一个原因
+    pub async fn select_skus(
+        &self,
+        ticket_id: &String,
+        perform_bases: &[PerformBase],
+        sessions: &[usize],
+    ) -> Result<(Vec<Sku>, Vec<String>, Vec<String>)> {
+        let mut perform_ids = Vec::new();
+        let mut perform_names = Vec::new();
+        let mut skus = Vec::new();
+        // 跳过的场次中最后一个的原因, 所有场次都不可用时返回
+        let mut skipped_err = None;
+        for &session in sessions {
+            // 超出范围的场次跳过, 尝试下一优先级场次
+            let perform_base = match session.checked_sub(1).and_then(|idx| perform_bases.get(idx)) {
+                Some(perform_base) => perform_base,
+                None => {
+                    let e = anyhow!(tr(Msg::SessionOutOfRange, &[&session, &perform_bases.len()]));
+                    warn!("{}", e);
+                    skipped_err = Some(e);
+                    continue;
+                }
+            };
+            // 选座类演出等场次可能没有可直接购买的演出, 跳过该场次并尝试下一优先级场次
+            let perform = match perform_base.performs.first() {
+                Some(perform) => perform,
+                None => {
+                    let e = anyhow!(tr(Msg::SessionNoPerform, &[&session, &perform_base.name]));
+                    warn!("{}", e);
+                    skipped_err = Some(e);
+                    continue;
+                }
+            };
+
+            info!("{}", tr(Msg::FetchingPerformInfo, &[]));
+            let perform_info = match self
+                .get_perform_info_cached(ticket_id, &perform.perform_id)
+                .await
+            {
+                Ok(info) => info,
+                Err(e) => {
+                    warn!(
+                        "{}",
+                        tr(Msg::PerformInfoFailed, &[&perform.perform_name, &format!("{:?}", e)])
+                    );
+                    skipped_err = Some(e);
+                    continue;
+                }
+            };
+            let sku_list = &perform_info.perform.sku_list;
+            let session_skus = match self.account.ticket.max_price {
+                // 该场次没有不超过价格上限的票档时尝试下一场次
+                Some(max_price) => match find_sku_by_max_price(sku_list, max_price) {
+                    Ok(sku) => vec![sku.clone()],
+                    Err(e) => {
+                        warn!("{}", e);
+                        skipped_err = Some(e);
+                        continue;
+                    }
+                },
+                // 超出范围的票档跳过
+                None => {
+                    let mut session_skus = Vec::new();
+                    for grade in self.account.ticket.grade_list() {
+                        match grade.checked_sub(1).and_then(|idx| sku_list.get(idx)) {
+                            Some(sku) => session_skus.push(sku.clone()),
+                            None => {
+                                let e = anyhow!(tr(Msg::GradeOutOfRange, &[&grade, &sku_list.len()]));
+                                warn!("{}", e);
+                                skipped_err = Some(e);
+                            }
+                        }
+                    }
+                    session_skus
+                }
+            };
+            if session_skus.is_empty() {
+                continue;
+            }
+            skus.extend(session_skus);
+            perform_ids.push(perform.perform_id.clone());
+            perform_names.push(perform.perform_name.clone());
+        }
+
+        if skus.is_empty() {
+            return Err(skipped_err.unwrap_or_else(|| anyhow!(tr(Msg::NoSkuFound, &[]))));
+        }
+        Ok((skus, perform_ids, perform_names))
+    }
+
+    // 获取场次/票档信息, 缓存未过期时直接返回缓存。库存状态可能已过期, 查询库存时使用get_perform_info
+    pub async fn get_perform_info_cached(
+        &self,
+        ticket_id: &String,
+        perform_id: &String,
+    ) -> Result<PerformInfo> {
+        if let Some(perform_info) = self.perform_info_cache.get(perform_id) {
+            debug!("使用缓存的场次信息, 场次ID:{}", perform_id);
+            return Ok(perform_info);
+        }
+        let perform_info = self.get_perform_info(ticket_id, perform_id).await?;
+        self.perform_info_cache.insert(perform_id, perform_info.clone());
+        Ok(perform_info)
     }
 
     // 获取场次/票档信息
@@ -223,61 +720,240 @@ impl DmTicket {
     ) -> Result<PerformInfo> {
         let start = Instant::now();
 
-        let url = "https://mtop.damai.cn/h5/mtop.alibaba.detail.subpage.getdetail/2.0/";
+        let url = self.client.endpoints.perform_info();
 
         let params = PerformParams::build()?;
 
         let data = PerformForm::build(ticket_id, perform_id)?;
 
-        let res = self.client.request(url, params, data, 0).await?;
-
-        debug!("获取演出票档信息:{:?}, 花费时间:{:?}", res, start.elapsed());
+        let mut perform_info: PerformInfo = self
+            .client
+            .request_typed(&url, params, data, DataField::Result)
+            .await?;
+        perform_info.fill_perform_name();
 
-        let perform_info: PerformInfo = serde_json::from_str(res.data["result"].as_str().unwrap())?;
+        debug!("获取演出票档信息成功, 花费时间:{:?}", start.elapsed());
 
         Ok(perform_info)
     }
 
-    // 购买流程
-    pub async fn buy(&self, item_id: &String, sku_id: &String, buy_num: usize, attempt: usize) -> Result<bool> {
-        
+    // 购买流程, 提交订单成功时返回订单号/支付链接, 未成功时返回None
+    // target为本次购买的场次/票档, 提交成功时记录
+    pub async fn buy(
+        &self,
+        item_id: &String,
+        sku_id: &String,
+        buy_num: usize,
+        attempt: usize,
+        target: &PurchasedTarget,
+    ) -> Result<Option<SubmitOrderResult>> {
+        // 已下单成功, 不再重复下单
+        if self.is_purchased() {
+            return Ok(self.order_result());
+        }
 
-        let order_info = match self.build_order(item_id, sku_id, buy_num, attempt).await {
-            Ok(data) => {
-                info!("成功生成订单...");
-                data
+        // 限制同时进行中的生成订单请求数, 等待许可的时间不计入耗时
+        let permit = self.inflight_builds.acquire().await?;
+        let start = Instant::now();
+        let order_info = self.build_order(item_id, sku_id, buy_num, attempt).await;
+        drop(permit);
+        let build_elapsed = match &order_info {
+            Ok(timed) => timed.elapsed,
+            Err(_) => start.elapsed(),
+        };
+        {
+            let mut stats = self.stats.lock().unwrap();
+            stats.attempts += 1;
+            stats.build_order.record(build_elapsed);
+        }
+        // 每次运行首次生成订单时记录本地时间与服务器时间, 用于排查发包过早/过晚
+        if !self.clock_skew_logged.swap(true, Ordering::SeqCst) {
+            self.log_clock_skew();
+        }
+
+        let order_info = match order_info {
+            Ok(timed) => {
+                logger::event(
+                    "order_built",
+                    json!({"item_id": item_id, "sku_id": sku_id, "buy_num": buy_num, "attempt": attempt, "elapsed_ms": build_elapsed.as_millis() as u64}),
+                    &tr(Msg::OrderBuilt, &[]),
+                );
+                timed.value
             }
             Err(e) => {
-                info!("生成订单失败, {}", e.to_string());
-                if e.to_string().contains(&DmApiError::SystemBusy.to_string()) {
-                    return Err(anyhow!(DmApiError::SystemBusy));
-                }
+                info!("{}", tr(Msg::OrderBuildFailed, &[&e]));
                 return Err(e);
             }
         };
 
-        let wait_for_submit_time = self.account.wait_for_submit_time;
+        // 试运行, 只生成订单不提交
+        if self.account.dry_run {
+            let order_data = self.build_order_data(&order_info)?;
+            let mut viewers = Vec::new();
+            for (key, item) in order_data.as_object().unwrap().iter() {
+                if !key.starts_with("dmViewer_") {
+                    continue;
+                }
+                for viewer in item["fields"]["viewerList"].as_array().unwrap_or(&Vec::new()) {
+                    if viewer["isUsed"].as_bool() == Some(true) {
+                        viewers.push(viewer["viewerName"].as_str().unwrap_or_default().to_string());
+                    }
+                }
+            }
+            debug!("[试运行] 订单数据:{}", order_data);
+            info!("[试运行] {}", order_info.summary());
+            info!(
+                "[试运行] 生成订单成功, 购票数量:{}, 已选实名观演人:{:?}, 跳过提交订单...",
+                buy_num, viewers
+            );
+            return Ok(None);
+        }
+
+        let order_summary = order_info.summary();
+        info!("{}", tr(Msg::OrderSummary, &[&order_summary]));
+
+        let wait_for_submit_time = match self.account.wait_for_submit_range() {
+            (min, max) if min < max => thread_rng().gen_range(min..=max),
+            (min, _) => min,
+        };
+        debug!("生成订单后等待{}毫秒再提交订单", wait_for_submit_time);
         tokio::time::sleep(Duration::from_millis(wait_for_submit_time)).await;
 
-        let res = self.submit_order(order_info).await?;
+        // 并发抢购时串行提交订单, 其他任务已下单成功则不再提交
+        let _submit_guard = self.submit_lock.lock().await;
+        if self.is_purchased() {
+            return Ok(self.order_result());
+        }
 
-        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
-            true => {
-                info!(
-                    "提交订单成功, 请尽快前往手机APP付款",
-                );
-                Ok(true)
+        // 提交失败时使用同一订单重试, 避免重新生成订单
+        let submit_retry_times = usize::from(self.account.submit_retry_times);
+        for submit_attempt in 0..=submit_retry_times {
+            let start = Instant::now();
+            let res = self.submit_order(&order_info, submit_attempt).await;
+            let submit_elapsed = match &res {
+                Ok(timed) => timed.elapsed,
+                Err(_) => start.elapsed(),
+            };
+            self.stats.lock().unwrap().submit_order.record(submit_elapsed);
+            let e = match res {
+                Ok(timed) => {
+                    let mut order_result = timed.value;
+                    order_result.total_price = order_summary.total_price.clone();
+                    if let Some(link) = order_result.payment_link() {
+                        self.event_sink.on_payment_link(&link);
+                    }
+                    *self.order_result.lock().unwrap() = Some(order_result.clone());
+                    *self.purchased_target.lock().unwrap() = Some(target.clone());
+                    // 跨票档凑票需提交多个订单, 由buy_across_grades在凑票结束后标记已下单
+                    if !self.account.ticket.split_across_grades {
+                        self.purchased.store(true, Ordering::SeqCst);
+                    }
+                    logger::event(
+                        "order_submitted",
+                        json!({"item_id": item_id, "sku_id": sku_id, "buy_num": buy_num, "attempt": attempt, "order_id": order_result.order_id, "elapsed_ms": submit_elapsed.as_millis() as u64}),
+                        &tr(Msg::OrderSubmitted, &[]),
+                    );
+                    return Ok(Some(order_result));
+                }
+                Err(e) => e,
+            };
+
+            // 提交结果未知(超时/网络错误等)时不重新提交, 避免重复下单
+            let error = match api_error(&e).cloned() {
+                Some(error) => error,
+                None => return Err(e),
+            };
+            info!("{}", tr(Msg::OrderSubmitFailed, &[&error]));
+            match error {
+                DmApiError::PurchaseLimit
+                | DmApiError::SessionExpired
+                | DmApiError::RiskControl
+                | DmApiError::CaptchaRequired(_)
+                | DmApiError::AlreadyOrdered(_)
+                | DmApiError::StockShortage => return Err(e),
+                // 已售空, 重试提交无意义
+                DmApiError::ProductExpired => return Ok(None),
+                _ if submit_attempt < submit_retry_times => {
+                    info!("{}", tr(Msg::SubmitRetrying, &[]));
+                }
+                _ => {}
             }
-            false => {
-                info!(
-                    "提交订单失败, 原因:{}",
-                    res.ret[0],
-                );
-                Ok(false)
+        }
+        Ok(None)
+    }
+
+    // 重置每次运行的状态, 刷新会话后重新运行时同样记录时间偏差
+    fn begin_run(&self) {
+        self.clock_skew_logged.store(false, Ordering::SeqCst);
+    }
+
+    // 输出本地时间、服务器时间(响应头Date)及偏差, 偏差过大时提示校时
+    fn log_clock_skew(&self) {
+        let local = Local::now();
+        let offset = match self.client.server_time_offset() {
+            Some(offset) => offset,
+            None => {
+                info!("首次生成订单, 本地时间:{}, 未获取到服务器时间", local.format("%H:%M:%S%.3f"));
+                return;
             }
+        };
+        let server = local + chrono::Duration::milliseconds(offset);
+        info!(
+            "首次生成订单, 本地时间:{}, 服务器时间:{}, 偏差:{}毫秒",
+            local.format("%H:%M:%S%.3f"),
+            server.format("%H:%M:%S%.3f"),
+            offset
+        );
+        // 已配置NTP或已按服务器时间校准时, 开抢时间已按校准后的时间计算
+        let skew = offset - self.time_sync.offset_millis();
+        if skew.abs() > CLOCK_SKEW_WARN_MS {
+            warn!(
+                "本地时间与服务器时间偏差过大:{}毫秒, 开抢时间可能不准, 建议配置ntp_server或同步系统时间!",
+                skew
+            );
         }
     }
 
+    // 等待停止信号: CTRL-C、其他账号已抢购成功或超出最长运行时间
+    async fn stop_signal(&self) {
+        let deadline = async {
+            match self.deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => futures::future::pending().await,
+            }
+        };
+        let ctrl_c = async {
+            match self.listen_ctrl_c {
+                true => {
+                    let _ = signal::ctrl_c().await;
+                }
+                false => futures::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = ctrl_c => {}
+            _ = self.cancel_token.cancelled() => {}
+            _ = deadline => {
+                warn!("运行时间已超过max_runtime_seconds, 时间预算耗尽, 停止运行...");
+            }
+        }
+    }
+
+    // 是否已下单成功
+    pub fn is_purchased(&self) -> bool {
+        self.purchased.load(Ordering::SeqCst)
+    }
+
+    // 提交订单成功的结果
+    pub fn order_result(&self) -> Option<SubmitOrderResult> {
+        self.order_result.lock().unwrap().clone()
+    }
+
+    // 提交订单成功的场次/票档
+    pub fn purchased_target(&self) -> Option<PurchasedTarget> {
+        self.purchased_target.lock().unwrap().clone()
+    }
+
     // 毫秒转时分秒
     pub fn ms_to_hms(&self, ms: i64) -> (u64, u64, f64) {
         let sec = ms as f64 / 1000.0;
@@ -288,85 +964,331 @@ impl DmTicket {
         (hour, min, sec)
     }
 
+    // 毫秒转天时分秒
+    pub fn ms_to_dhms(&self, ms: i64) -> (u64, u64, u64, f64) {
+        let (hour, min, sec) = self.ms_to_hms(ms);
+        (hour / 24, hour % 24, min, sec)
+    }
+
     // 尝试多次购买
     pub async fn multiple_buy_attempts(
         &self,
         item_id: &String,
         sku_id: &String,
         buy_num: Option<usize>,
+        target: &PurchasedTarget,
     ) -> Result<bool> {
-        let buy_num = match buy_num {
-            Some(num) => num,
-            None => self.account.ticket.num,
-        };
+        let buy_num = effective_buy_num(
+            buy_num.unwrap_or(self.account.ticket.num),
+            self.purchase_limit,
+        )?;
+        info!("{}", tr(Msg::BuyNum, &[&buy_num]));
         let retry_times = self.account.retry_times;
         let mut _run_time: u64 = 0;
         let mut min_time: u64 = 9999;
         for attempt in 0..retry_times {
             let start = Instant::now();
-            match self.buy(item_id, sku_id, buy_num, usize::from(attempt)).await {
-                Ok(res) => {
-                    if res {
+            match self.buy(item_id, sku_id, buy_num, usize::from(attempt), target).await {
+                Ok(order_result) => {
+                    if order_result.is_some() {
+                        self.event_sink.on_attempt(attempt, start.elapsed(), true);
                         return Ok(true);
                     }
                 }
-                Err(e) => {
-                    // B-00203-200-034::您选购的商品信息已过期，请重新查询
-                    
-                    if e.to_string()
-                        .contains(&DmApiError::ProductEpired.to_string())
-                    {
+                // 订单可能已创建或实名观演人配置错误, 停止抢购
+                Err(e) if should_stop(&e) => {
+                    error!("{}", e);
+                    self.notify_error(&e.to_string()).await;
+                    return Err(e);
+                }
+                Err(e) => match api_error(&e) {
+                    // 已有该演出的订单, 继续重试可能重复下单
+                    Some(DmApiError::AlreadyOrdered(order_ref)) => {
+                        match order_ref {
+                            Some(order_ref) => warn!("{}", tr(Msg::AlreadyOrderedRef, &[order_ref])),
+                            None => warn!("{}", tr(Msg::AlreadyOrdered, &[])),
+                        }
                         return Err(e);
                     }
-                    if e.to_string().contains(&DmApiError::SystemBusy.to_string()) {
+                    // 触发滑块/验证码, 不是库存问题, 通知用户完成验证
+                    Some(DmApiError::CaptchaRequired(url)) => {
+                        // 验证地址一并通知/显示, 方便用户直接打开
+                        let message = match url {
+                            Some(url) => tr(Msg::CaptchaRequiredUrl, &[url]),
+                            None => tr(Msg::CaptchaRequired, &[]),
+                        };
+                        error!("{}", message);
+                        self.notify_error(&message).await;
+                        let pause_secs = self.account.captcha_pause_secs;
+                        if pause_secs == 0 {
+                            return Err(e);
+                        }
+                        let status = tr(Msg::CaptchaPause, &[&pause_secs]);
+                        info!("{}", status);
+                        self.event_sink.on_status(&status);
+                        tokio::select! {
+                            _ = self.stop_signal() => {
+                                info!("{}", self.stats());
+                                return Err(anyhow!(DmTicketError::Cancelled));
+                            }
+                            _ = tokio::time::sleep(Duration::from_secs(pause_secs)) => {}
+                        }
+                        continue;
+                    }
+                    // 商品售空/库存不足/系统繁忙/限购/登录过期, 重试无意义
+                    Some(
+                        DmApiError::ProductExpired
+                        | DmApiError::StockShortage
+                        | DmApiError::SystemBusy
+                        | DmApiError::PurchaseLimit
+                        | DmApiError::SessionExpired,
+                    ) => {
                         return Err(e);
                     }
-                }
+                    // 触发风控, 刷新bx-umidtoken后重试
+                    Some(DmApiError::RiskControl) => {
+                        warn!("触发风控, 正在刷新bx-umidtoken...");
+                        if let Err(e) = self.client.refresh_bx_token().await {
+                            warn!("刷新bx-umidtoken失败, 原因:{:?}", e);
+                        }
+                    }
+                    _ => {}
+                },
             }
             let elapsed = start.elapsed().as_millis() as u64;
             _run_time += elapsed;
             if elapsed < min_time {
                 min_time = elapsed;
             }
-            // 根据奇偶次数等待不同的重试间隔时间
-            let retry_interval = if attempt % 2 == 0 {
-                RETRY_INTERVAL_MS
-            } else {
-                (((attempt+1) / 2) as u64) * 5500 - _run_time - min_time
-            };
+            // 根据重试策略计算重试间隔时间
+            let retry_interval = with_jitter(
+                self.account.retry_strategy.interval(
+                    attempt,
+                    self.account.retry_interval,
+                    _run_time,
+                    min_time,
+                ),
+                self.account.retry_jitter_ms,
+            );
             _run_time += retry_interval;
+            self.event_sink.on_attempt(attempt, start.elapsed(), false);
             info!("此{}次抢购花费时间:{:?} 等待{:?}",attempt, start.elapsed(), retry_interval);
-            // 重试间隔
-            tokio::time::sleep(Duration::from_millis(retry_interval)).await;
+            // 重试间隔, 期间可CTRL-C退出
+            tokio::select! {
+                _ = self.stop_signal() => {
+                    info!("{}", self.stats());
+                    return Err(anyhow!(DmTicketError::Cancelled));
+                }
+                _ = tokio::time::sleep(Duration::from_millis(retry_interval)) => {}
+            }
         }
         Ok(false)
     }
 
+    // 并发抢购, 同时发起parallel_builds个生成/提交订单流程, 任一成功即取消其余流程
+    // 注意: 并发请求会成倍增加请求频率, 更容易触发风控(滑块验证/账号限制), 仅建议在热门场次使用
+    pub async fn multiple_buy_attempts_parallel(
+        &self,
+        item_id: &String,
+        sku_id: &String,
+        buy_num: Option<usize>,
+        target: &PurchasedTarget,
+    ) -> Result<bool> {
+        let parallel_builds = self.account.parallel_builds.max(1);
+        if parallel_builds == 1 {
+            return self.multiple_buy_attempts(item_id, sku_id, buy_num, target).await;
+        }
+
+        let parallel_stagger = self.account.parallel_stagger;
+        let mut pipelines = (0..parallel_builds)
+            .map(|i| async move {
+                // 错开各流程的发起时间
+                tokio::time::sleep(Duration::from_millis(parallel_stagger * i as u64)).await;
+                self.multiple_buy_attempts(item_id, sku_id, buy_num, target).await
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        // 任一流程成功立即返回, 否则等待所有流程结束, 返回优先级最高的错误
+        let mut error: Option<anyhow::Error> = None;
+        while let Some(res) = pipelines.next().await {
+            match res {
+                Ok(true) => return Ok(true),
+                Ok(false) => {}
+                Err(e) => {
+                    let higher = match &error {
+                        Some(prev) => error_priority(&e) > error_priority(prev),
+                        None => true,
+                    };
+                    if higher {
+                        error = Some(e);
+                    }
+                }
+            }
+        }
+        match error {
+            Some(e) => Err(e),
+            None => Ok(false),
+        }
+    }
+
+    // 获取门票的场次/票档列表, 用于配置sessions/grade
+    pub async fn info(&self) -> Result<TicketOverview> {
+        let ticket_id = self.account.ticket.id.clone();
+        let ticket_info = self.get_ticket_info(ticket_id.clone()).await?;
+        let item = &ticket_info.detail_view_component_map.item;
+
+        let mut sessions = Vec::new();
+        for (i, perform_base) in item.item.perform_bases.iter().enumerate() {
+            let mut session = SessionOverview {
+                index: i + 1,
+                name: perform_base.name.clone(),
+                perform_id: String::new(),
+                grades: Vec::new(),
+            };
+            if let Some(perform) = perform_base.performs.first() {
+                session.perform_id = perform.perform_id.clone();
+                let perform_info = self.get_perform_info(&ticket_id, &perform.perform_id).await?;
+                session.grades = perform_info
+                    .perform
+                    .sku_list
+                    .iter()
+                    .enumerate()
+                    .map(|(j, sku)| GradeOverview {
+                        index: j + 1,
+                        sku_id: sku.sku_id.clone(),
+                        price_name: sku.price_name.clone(),
+                        price: sku.price_value(),
+                        salable: sku.sku_salable.contains("true"),
+                    })
+                    .collect();
+            }
+            sessions.push(session);
+        }
+
+        Ok(TicketOverview {
+            ticket_id,
+            ticket_name: item.static_data.item_base.item_name.clone(),
+            sell_start_time: item.item.sell_start_time_str.clone(),
+            sessions,
+        })
+    }
+
+    // 回放保存的原始响应, 依次执行获取门票信息/场次信息/生成订单/提交订单, 用于复现接口返回异常
+    pub async fn replay(&self) -> Result<()> {
+        let ticket_id = self.account.ticket.id.clone();
+
+        let ticket_info = self.get_ticket_info(ticket_id.clone()).await?;
+        let item = &ticket_info.detail_view_component_map.item;
+        info!("[回放] 获取门票信息成功, 门票名称:{}", item.static_data.item_base.item_name);
+
+        let session = self.account.ticket.session_list().first().copied().unwrap_or(1);
+        let perform = item
+            .item
+            .perform_bases
+            .get(session.saturating_sub(1))
+            .or_else(|| item.item.perform_bases.first())
+            .and_then(|base| base.performs.first())
+            .ok_or_else(|| anyhow!("[回放] 门票信息中没有场次, 或该场次没有可购买的演出"))?;
+        let perform_info = self.get_perform_info(&ticket_id, &perform.perform_id).await?;
+        info!("[回放] 获取场次信息成功, 场次名称:{}", perform.perform_name);
+
+        let sku_list = &perform_info.perform.sku_list;
+        let grade = self.account.ticket.grade_list().first().copied().unwrap_or(1);
+        let sku = sku_list
+            .get(grade.saturating_sub(1))
+            .or_else(|| sku_list.first())
+            .ok_or_else(|| anyhow!("[回放] 场次信息中没有票档"))?;
+        let order_info = self
+            .build_order(&sku.item_id, &sku.sku_id, self.account.ticket.num, 0)
+            .await?;
+        info!(
+            "[回放] 生成订单成功, 票档名称:{}, 耗时:{:?}",
+            sku.price_name, order_info.elapsed
+        );
+
+        let res = self.submit_order(&order_info.value, 0).await?;
+        info!(
+            "[回放] 提交订单成功, 订单号:{}",
+            res.value.order_id.as_deref().unwrap_or("未知")
+        );
+        Ok(())
+    }
+
     // 程序入口
-    pub async fn run(&self) -> Result<()> {
-        info!("正在检查用户信息...");
+    // 返回是否抢购成功
+    pub async fn run(&mut self) -> Result<bool> {
+        self.deadline = self
+            .account
+            .max_runtime_seconds
+            .map(|seconds| tokio::time::Instant::now() + Duration::from_secs(seconds));
+
+        let res = match self.run_once().await {
+            // 会话过期时刷新会话(重新读取cookie并获取token)后重新运行一次
+            Err(e)
+                if self.account.auto_relogin
+                    && api_error(&e) == Some(&DmApiError::SessionExpired) =>
+            {
+                warn!("{}", tr(Msg::SessionExpiredRerun, &[]));
+                match self.refresh_session().await {
+                    Ok(_) => self.run_once().await,
+                    Err(e) => Err(e),
+                }
+            }
+            res => res,
+        };
+        // 退出前(包括出错、CTRL-C/取消)保存cookie, 下次启动时复用
+        self.client.save_cookie_store();
+        res
+    }
+
+    // 运行一次完整的抢购流程
+    async fn run_once(&mut self) -> Result<bool> {
+        self.begin_run();
+        // 定时启动, 等待到指定时间
+        if let Some(scheduled_start) = self.account.scheduled_start {
+            let wait_millis = scheduled_start.timestamp_millis() - self.time_sync.now_millis();
+            if wait_millis > 0 {
+                info!("定时启动, 将于{}开始运行...", scheduled_start.format("%Y-%m-%d %H:%M:%S"));
+                tokio::select! {
+                    _ = self.stop_signal() => {
+                        return Ok(false);
+                    }
+                    _ = tokio::time::sleep(Duration::from_millis(wait_millis as u64)) => {}
+                }
+            }
+        }
+
+        let start = Instant::now();
+        info!("{}", tr(Msg::CheckingUserInfo, &[]));
         let user_info = match self.get_user_info().await {
             Ok(info) => info,
             Err(e) => {
-                if e.to_string().contains("FAIL_SYS_SESSION_EXPIRED::Session") {
-                    error!("获取用户信息失败, cookie已过期, 请重新登陆!");
+                if api_error(&e) == Some(&DmApiError::SessionExpired) {
+                    error!("{}", tr(Msg::CookieExpired, &[]));
+                    self.notify_error(&tr(Msg::CookieExpiredNotify, &[])).await;
                 } else {
-                    error!("获取用户信息失败, 原因:{:?}", e);
+                    error!("{}", tr(Msg::UserInfoFailed, &[&format!("{:?}", e)]));
                 }
 
-                return Ok(());
+                return Err(e);
             }
         };
+
+        // 未配置NTP时, 使用服务器时间校准本地时间
+        if self.account.ntp_server.is_none() {
+            if let Some(offset) = self.client.server_time_offset() {
+                info!("本地时间与服务器时间偏差:{}毫秒", offset);
+                self.time_sync = TimeSync::new(offset);
+            }
+        }
+
         let ticket_id = self.account.ticket.id.clone();
-        let perfomr_idx = self.account.ticket.sessions - 1; // 场次索引
-        let sku_idx = self.account.ticket.grade - 1; // 票档索引
-        let priority_purchase_time = self.account.ticket.priority_purchase_time;
 
-        info!("正在获取演唱会信息...");
-        let ticket_info = match self.get_ticket_info(ticket_id.clone()).await {
+        info!("{}", tr(Msg::FetchingTicketInfo, &[]));
+        let ticket_info = match self.get_ticket_info_cached(ticket_id.clone()).await {
             Ok(info) => info,
             Err(e) => {
-                info!("获取演唱会信息失败, {:?}", e);
+                info!("{}", tr(Msg::TicketInfoFailed, &[&format!("{:?}", e)]));
                 return Err(e);
             }
         };
@@ -378,29 +1300,30 @@ impl DmTicket {
             .item_base
             .item_name;
 
-        let perform_id = ticket_info
-            .detail_view_component_map
-            .item
-            .item
-            .perform_bases[perfomr_idx]
-            .performs[0]
-            .perform_id
-            .clone();
+        self.purchase_limit = ticket_info.detail_view_component_map.item.item.purchase_limit();
+        if let Some(limit) = self.purchase_limit {
+            info!("门票限购数量:{}", limit);
+        }
 
-        let perform_name = ticket_info
-            .detail_view_component_map
-            .item
-            .item
-            .perform_bases[perfomr_idx]
-            .performs[0]
-            .perform_name
-            .clone();
+        let perform_bases = &ticket_info.detail_view_component_map.item.item.perform_bases;
 
-        info!("正在获取场次/票档信息...");
-        let perform_info = self.get_perform_info(&ticket_id, &perform_id).await?;
-        let sku_id = perform_info.perform.sku_list[sku_idx].sku_id.clone();
-        let sku_name = perform_info.perform.sku_list[sku_idx].price_name.clone();
-        let item_id = perform_info.perform.sku_list[sku_idx].item_id.clone();
+        // 按场次优先级获取场次/票档信息
+        let sessions = match &self.account.ticket.session_date {
+            Some(date) => vec![find_session_by_date(perform_bases, date)?],
+            None => self.account.ticket.session_list(),
+        };
+        let (skus, perform_ids, perform_names) =
+            self.select_skus(&ticket_id, perform_bases, &sessions).await?;
+
+        let perform_id = perform_ids[0].clone();
+        let perform_name = perform_names.join(" > ");
+        let mut sku_names = Vec::new();
+        for sku in skus.iter() {
+            if !sku_names.contains(&sku.price_name) {
+                sku_names.push(sku.price_name.clone());
+            }
+        }
+        let sku_name = sku_names.join(" > ");
 
         let start_time_str = ticket_info
             .detail_view_component_map
@@ -414,6 +1337,7 @@ impl DmTicket {
             .item
             .sell_start_timestamp
             .parse::<i64>()?;
+        self.sell_start_timestamp = Some(start_timestamp);
 
         let request_time = self.account.request_time;
 
@@ -421,129 +1345,520 @@ impl DmTicket {
             start_timestamp = request_time
         }
 
-        println!(
-            "\r\n\t账号备注: {}\n\t账号昵称: {}\n\t门票名称: {}\n\t场次名称: {}\n\t票档名称: {}\n\t开售时间: {}\n",
-            self.account.remark, user_info.nickname, ticket_name, perform_name, sku_name, start_time_str
+        info!(
+            "\n\t{}\n",
+            tr(
+                Msg::Summary,
+                &[
+                    &self.account.remark,
+                    &user_info.nickname,
+                    &ticket_name,
+                    &perform_name,
+                    &sku_name,
+                    &start_time_str
+                ]
+            )
         );
 
-        let local: DateTime<Local> = Local::now();
-        let current_timestamp = local.timestamp_millis();
+        if self.account.monitor {
+            return self
+                .monitor(&ticket_id, &perform_id, &ticket_name, &perform_names[0])
+                .await;
+        }
+
+        let target = match self
+            .purchase(start_timestamp, &skus, ticket_id, &perform_ids)
+            .await
+        {
+            Ok(target) => target,
+            Err(e) if is_cancelled(&e) => None,
+            Err(e) => return Err(e),
+        };
+        let is_success = target.is_some();
+
+        self.stats.lock().unwrap().elapsed = start.elapsed();
+        info!("{}", self.stats());
+
+        if let Some(target) = target {
+            self.event_sink.on_success(&target);
+            // 优先使用实际抢到的场次/票档, 未知时使用配置的场次/票档
+            let (perform_name, sku_name) = match target.sku_name.is_empty() {
+                true => (perform_name, sku_name),
+                false => {
+                    info!(
+                        "{}",
+                        tr(
+                            Msg::PurchasedTarget,
+                            &[&target.perform_name, &target.sku_name, &target.price]
+                        )
+                    );
+                    (target.perform_name, target.sku_name)
+                }
+            };
+            self.save_order_record(&ticket_name, &perform_name, &sku_name);
+            let mut message = NotifyMessage::new(
+                &self.account.remark,
+                &ticket_name,
+                &perform_name,
+                &sku_name,
+            );
+            message.total_price = self
+                .order_result
+                .lock()
+                .unwrap()
+                .as_ref()
+                .and_then(|result| result.total_price.clone());
+            self.notify(&message).await;
+        }
+        Ok(is_success)
+    }
+
+    // 保存下单记录, 便于多账号运行后查找订单
+    fn save_order_record(&self, ticket_name: &str, perform_name: &str, sku_name: &str) {
+        let order_result = self.order_result.lock().unwrap().clone().unwrap_or_default();
+        let record = OrderRecord::new(
+            &self.account.remark,
+            ticket_name,
+            perform_name,
+            sku_name,
+            order_result.order_id,
+            order_result.pay_url,
+        );
+        let path = &self.account.order_record_file;
+        match record.append_to(path) {
+            Ok(_) => info!("下单记录已保存至:{:?}, 订单号:{:?}", path, record.order_id),
+            Err(e) => warn!("保存下单记录至:{:?}失败, 原因:{:?}", path, e),
+        }
+    }
+
+    // 抢购流程, 抢购成功时返回抢到的场次/票档
+    async fn purchase(
+        &mut self,
+        start_timestamp: i64,
+        skus: &[Sku],
+        ticket_id: String,
+        perform_ids: &[String],
+    ) -> Result<Option<PurchasedTarget>> {
+        let priority_purchase_time = self.account.ticket.priority_purchase_time;
+        let current_timestamp = self.time_sync.now_millis();
 
         match current_timestamp > start_timestamp {
-            true => {
-                if let Err(e) = self.buy_it_now(&item_id, &sku_id).await {
-                    if e.to_string()
-                        .contains(&DmApiError::ProductEpired.to_string())
-                    {
+            true => match self.buy_it_now(skus).await {
+                Ok(res) => Ok(res),
+                Err(e) => {
+                    if api_error(&e) == Some(&DmApiError::ProductExpired) {
                         let grace_period_millis =
                             self.account.ticket.pick_up_leaks.grace_period_minutes * 60 * 1000;
-                        if (current_timestamp - start_timestamp) > grace_period_millis {
-                            return Ok(());
+                        if (current_timestamp - start_timestamp) > grace_period_millis
+                            || !self.account.ticket.pick_up_leaks.enabled
+                        {
+                            return Ok(None);
                         }
-                        info!("商品已售空, 去捡漏...\n");
-                        return self.pick_up_leaks(ticket_id, perform_id).await;
+                        info!("{}\n", tr(Msg::SoldOutPickUpLeaks, &[]));
+                        return self.pick_up_leaks_multi(ticket_id, perform_ids).await;
                     }
-                    if e.to_string()
-                        .contains(&DmApiError::SystemBusy.to_string())
-                    {
-                        info!("cookie 失效\n");
-                        let args: Vec<String> = env::args().collect();
-                        println!("{:?}", args);
-                        let output = Command::new("python")
-                        .arg("../Automatic_ticket_purchase/Automatic_ticket_purchase.py")
-                        .arg("--login_id")
-                        .arg(args[1].clone())
-                        .output()
-                        .expect("Failed to execute Python script.");
-                
-                        if output.status.success() {
-                            let stdout = String::from_utf8_lossy(&output.stdout);
-                            println!("Python script executed successfully. Output:\n{}", stdout);
-                        } else {
-                            let stderr = String::from_utf8_lossy(&output.stderr);
-                            println!("Python script failed. Error:\n{}", stderr);
-                        }
-                        // self.buy_it_now(&item_id, &sku_id).await;
-                        return Ok(());
+                    if api_error(&e) == Some(&DmApiError::SystemBusy) {
+                        info!("{}\n", tr(Msg::SessionRefreshing, &[]));
+                        self.refresh_session().await?;
+                        return self.buy_it_now(skus).await;
+                    }
+                    if should_stop(&e) {
+                        return Err(e);
                     }
+                    Ok(None)
                 }
-            }
+            },
             false => {
-                let res = self.wait_for_buy(start_timestamp, &item_id, &sku_id).await;
+                let res = self
+                    .wait_for_buy(start_timestamp, skus, &ticket_id, &perform_ids[0])
+                    .await;
                 match res {
-                    Ok(is_succes) => {
-                        if is_succes {
-                            return Ok(());
-                        }
-                    }
-                    Err(e) => {
-                        if e.to_string().contains("退出") {
-                            return Ok(());
-                        }
-                    }
+                    Ok(Some(target)) => return Ok(Some(target)),
+                    Err(e) if is_cancelled(&e) => return Ok(None),
+                    Err(e) if should_stop(&e) => return Err(e),
+                    _ => {}
                 };
                 if priority_purchase_time > 0 {
                     let start_timestamp = start_timestamp + priority_purchase_time * 60 * 1000;
-                    info!("优先购已结束, 等待正式开抢...\n\n");
-                    if let Ok(res) = self.wait_for_buy(start_timestamp, &item_id, &sku_id).await {
-                        if res {
-                            return Ok(());
+                    info!("{}\n\n", tr(Msg::PriorityPurchaseEnded, &[]));
+                    match self
+                        .wait_for_buy(start_timestamp, skus, &ticket_id, &perform_ids[0])
+                        .await
+                    {
+                        Ok(Some(target)) => return Ok(Some(target)),
+                        Err(e) if is_cancelled(&e) => return Ok(None),
+                        Err(e) if should_stop(&e) => return Err(e),
+                        _ => {}
+                    }
+                }
+                if !self.account.ticket.pick_up_leaks.enabled {
+                    info!("{}", tr(Msg::LeakDisabledExit, &[]));
+                    return Ok(None);
+                }
+                info!("\t{}", tr(Msg::NotGotPickUpLeaks, &[]));
+                self.pick_up_leaks_multi(ticket_id, perform_ids).await
+            }
+        }
+    }
+
+    // 抢购耗时统计
+    pub fn stats(&self) -> RunStats {
+        *self.stats.lock().unwrap()
+    }
+
+    // 发送通知, 通知失败不影响抢购流程
+    pub async fn notify(&self, message: &NotifyMessage) {
+        notify_all(&self.notifiers, message).await;
+    }
+
+    // 发送异常通知, 同时显示到状态事件
+    pub async fn notify_error(&self, reason: &str) {
+        self.event_sink.on_error(reason);
+        if self.account.notify.notify_on_error {
+            self.notify(&NotifyMessage::error(&self.account.remark, reason))
+                .await;
+        }
+    }
+
+    // 按票档优先级依次购买, 当前票档失败或售空时尝试下一票档
+    pub async fn buy_by_priority(&self, skus: &[Sku]) -> Result<Option<PurchasedTarget>> {
+        if self.account.ticket.split_across_grades {
+            return self.buy_across_grades(skus).await;
+        }
+        let mut last_err = None;
+        for sku in skus {
+            if self.is_purchased() {
+                return Ok(self.purchased_target());
+            }
+            let target = PurchasedTarget::new(&sku.perform_name, sku);
+            match self
+                .multiple_buy_attempts_parallel(&sku.item_id, &sku.sku_id, None, &target)
+                .await
+            {
+                Ok(true) => {
+                    info!("{}", tr(Msg::GradeSuccess, &[&sku.price_name]));
+                    return Ok(self.purchased_target());
+                }
+                Ok(false) => {
+                    info!("{}", tr(Msg::GradeFailed, &[&sku.price_name]));
+                }
+                Err(e)
+                    if matches!(
+                        api_error(&e),
+                        Some(DmApiError::ProductExpired | DmApiError::StockShortage)
+                    ) =>
+                {
+                    info!("{}", tr(Msg::GradeSoldOut, &[&sku.price_name]));
+                    last_err = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        match last_err {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+
+    // 跨票档凑票, 按票档优先级分多个订单购买, 直到凑够ticket.num张或所有票档无票
+    // 每个订单的数量不超过剩余数量, 保证总数不超过ticket.num
+    pub async fn buy_across_grades(&self, skus: &[Sku]) -> Result<Option<PurchasedTarget>> {
+        let total = effective_buy_num(self.account.ticket.num, self.purchase_limit)?;
+        let mut allocations: Vec<Allocation> = Vec::new();
+        let mut bought = 0;
+        let mut last_err = None;
+
+        'grades: for sku in skus {
+            let target = PurchasedTarget::new(&sku.perform_name, sku);
+            let mut num = total - bought;
+            while num > 0 {
+                *self.viewer_range.lock().unwrap() = Some((bought, num));
+                match self
+                    .multiple_buy_attempts(&sku.item_id, &sku.sku_id, Some(num), &target)
+                    .await
+                {
+                    Ok(true) => {
+                        let order_result = self.order_result().unwrap_or_default();
+                        let confirmed = order_result.order_id.is_some();
+                        let allocation = Allocation {
+                            perform_name: sku.perform_name.clone(),
+                            sku_name: sku.price_name.clone(),
+                            num,
+                            order_id: order_result.order_id,
+                            total_price: order_result.total_price,
+                        };
+                        // 未返回订单号时无法确认购买数量, 停止凑票, 避免超出购买数量
+                        if !confirmed {
+                            warn!("{}", tr(Msg::SplitUnconfirmed, &[&allocation]));
+                            allocations.push(allocation);
+                            break 'grades;
+                        }
+                        bought += num;
+                        info!("{}", tr(Msg::SplitBought, &[&allocation, &bought, &total]));
+                        allocations.push(allocation);
+                        num = total - bought;
+                    }
+                    // 重试次数用完, 尝试下一票档
+                    Ok(false) => break,
+                    // 商品信息过期(已售空), 尝试下一票档
+                    Err(e) if api_error(&e) == Some(&DmApiError::ProductExpired) => {
+                        info!("{}", tr(Msg::GradeSoldOut, &[&sku.price_name]));
+                        last_err = Some(e);
+                        break;
+                    }
+                    // 库存不足, 减少购买数量后重试
+                    Err(e) if api_error(&e) == Some(&DmApiError::StockShortage) => {
+                        info!("{}", tr(Msg::SplitReduceNum, &[&sku.price_name, &num]));
+                        num -= 1;
+                        last_err = Some(e);
+                    }
+                    Err(e) if is_cancelled(&e) => break 'grades,
+                    Err(e) => {
+                        if allocations.is_empty() {
+                            *self.viewer_range.lock().unwrap() = None;
+                            return Err(e);
                         }
+                        warn!("{}", tr(Msg::SplitInterrupted, &[&format!("{:?}", e)]));
+                        break 'grades;
                     }
                 }
-                info!("\t未能抢到票, 去捡漏...");
-                self.pick_up_leaks(ticket_id, perform_id).await?;
             }
+            if bought >= total {
+                break;
+            }
+        }
+        *self.viewer_range.lock().unwrap() = None;
+
+        if allocations.is_empty() {
+            return match last_err {
+                Some(e) => Err(e),
+                None => Ok(None),
+            };
+        }
+        // 已提交订单, 其他流程不再下单
+        self.purchased.store(true, Ordering::SeqCst);
+        info!("{}", tr(Msg::SplitFinished, &[&bought, &total]));
+        for allocation in allocations.iter() {
+            info!("\t{}", allocation);
+        }
+
+        let mut perform_names: Vec<String> = Vec::new();
+        for allocation in allocations.iter() {
+            if !perform_names.contains(&allocation.perform_name) {
+                perform_names.push(allocation.perform_name.clone());
+            }
+        }
+        let target = PurchasedTarget {
+            perform_name: perform_names.join(" / "),
+            sku_id: String::new(),
+            sku_name: allocations
+                .iter()
+                .map(|allocation| format!("{}×{}", allocation.sku_name, allocation.num))
+                .collect::<Vec<_>>()
+                .join(" + "),
+            price: allocations
+                .iter()
+                .map(|allocation| {
+                    allocation.total_price.clone().unwrap_or_else(|| tr(Msg::Unknown, &[]))
+                })
+                .collect::<Vec<_>>()
+                .join(" + "),
         };
-        Ok(())
+        *self.purchased_target.lock().unwrap() = Some(target.clone());
+        Ok(Some(target))
     }
 
     // 立即购买
-    pub async fn buy_it_now(&self, item_id: &String, sku_id: &String) -> Result<bool> {
-        self.multiple_buy_attempts(item_id, sku_id, None).await
+    pub async fn buy_it_now(&self, skus: &[Sku]) -> Result<Option<PurchasedTarget>> {
+        self.buy_by_priority(skus).await
+    }
+
+    // 预热DNS/TLS连接并校验token, 使开抢时的请求复用已建立的连接
+    pub async fn warmup(&self) {
+        let start = Instant::now();
+        match self.get_user_info().await {
+            Ok(_) => info!("\n\t连接预热完成, 耗时:{}毫秒", start.elapsed().as_millis()),
+            Err(e) => warn!(
+                "\n\t连接预热失败, 耗时:{}毫秒, 原因:{:?}",
+                start.elapsed().as_millis(),
+                e
+            ),
+        }
+    }
+
+    // 测量获取场次信息的请求耗时中位数, 单位毫秒
+    pub async fn calibrate_latency(&self, ticket_id: &String, perform_id: &String) -> Option<i64> {
+        let mut latencies = Vec::new();
+        for _ in 0..CALIBRATION_SAMPLES {
+            let start = Instant::now();
+            if self.get_perform_info(ticket_id, perform_id).await.is_ok() {
+                latencies.push(start.elapsed().as_millis() as i64);
+            }
+        }
+        if latencies.is_empty() {
+            warn!("自动校准early_submit_time失败, 使用配置值:{}毫秒", self.account.early_submit_time);
+            return None;
+        }
+        latencies.sort_unstable();
+        Some(latencies[latencies.len() / 2])
     }
 
     // 等待开售
     pub async fn wait_for_buy(
         &self,
         start_timestamp: i64,
-        item_id: &String,
-        sku_id: &String,
-    ) -> Result<bool> {
+        skus: &[Sku],
+        ticket_id: &String,
+        perform_id: &String,
+    ) -> Result<Option<PurchasedTarget>> {
         let (s, r) = async_channel::unbounded::<bool>();
 
         let interval = self.account.interval;
-        let earliest_submit_time = self.account.early_submit_time;
+        let mut earliest_submit_time = self.account.early_submit_time;
+        let precise_wait_threshold = self.account.precise_wait_threshold;
+        let warmup_lead_time = self.account.warmup_lead_time;
+        let mut warmed_up = warmup_lead_time <= 0;
+        let mut calibrated = !self.account.auto_early_submit_time;
 
         // 轮询等待开抢
         loop {
+            let mut remaining = start_timestamp - earliest_submit_time - self.time_sync.now_millis();
+
+            // 开抢前warmup_lead_time毫秒预热连接, 已到发包时间则不再预热, 避免推迟首次提交
+            if !warmed_up && remaining <= warmup_lead_time {
+                warmed_up = true;
+                if remaining > 0 {
+                    self.warmup().await;
+                    remaining = start_timestamp - earliest_submit_time - self.time_sync.now_millis();
+                }
+            }
+
+            // 预热后自动校准early_submit_time, 未启用预热时开始等待即校准
+            if !calibrated && warmed_up {
+                calibrated = true;
+                if remaining > 0 {
+                    if let Some(latency) = self.calibrate_latency(ticket_id, perform_id).await {
+                        earliest_submit_time = latency;
+                        info!("自动校准early_submit_time:{}毫秒", latency);
+                    }
+                    remaining = start_timestamp - earliest_submit_time - self.time_sync.now_millis();
+                }
+            }
+
+            // spin模式下最后final_spin_ms毫秒忙等待, 到达发包时间后立即抢购
+            if self.account.spin_mode == SpinMode::Spin && remaining <= self.account.final_spin_ms {
+                let fire_timestamp = start_timestamp - earliest_submit_time;
+                let spin = async {
+                    while self.time_sync.now_millis() < fire_timestamp {
+                        // 让出执行权, 避免独占工作线程, 同时可响应CTRL-C/取消/超时
+                        tokio::task::yield_now().await;
+                    }
+                };
+                tokio::select! {
+                    _ = self.stop_signal() => {
+                        return Err(anyhow!(DmTicketError::Cancelled));
+                    }
+                    _ = spin => {}
+                }
+                return self.buy_by_priority(skus).await;
+            }
+
+            // 距离开抢不足precise_wait_threshold毫秒时, 每1毫秒检查一次, 尽量准时发出第一个请求
+            let wait = match remaining > precise_wait_threshold {
+                true => (interval as i64).min(remaining - precise_wait_threshold).max(1),
+                false => 1,
+            };
             tokio::select! {
-                _ = signal::ctrl_c() => {
-                    return Err(anyhow!("CTRL-C, 退出程序..."));
+                _ = self.stop_signal() => {
+                    return Err(anyhow!(DmTicketError::Cancelled));
                 }
-                _ = tokio::time::sleep(Duration::from_millis(interval)) => {
-                    let local: DateTime<Local> = Local::now();
-                    let millis = local.timestamp_millis();
+                _ = tokio::time::sleep(Duration::from_millis(wait as u64)) => {
+                    let millis = self.time_sync.now_millis();
                     let time_left_millis = start_timestamp - millis;
                     if time_left_millis <= earliest_submit_time {
                         let _ = s.send(true).await;
                     }else{
-                        let (hours, minutes, seconds) = self.ms_to_hms(time_left_millis);
-                        print!("\r\t开抢倒计时:{}小时:{}分钟:{:.3}秒\t", hours, minutes, seconds);
-                        let _ = io::stdout().flush();
+                        self.event_sink.on_countdown(time_left_millis);
                     }
 
                 }
 
                 _ = r.recv() => {
-                    return self.multiple_buy_attempts(item_id, sku_id, None).await
+                    return self.buy_by_priority(skus).await
                 }
             }
         }
     }
 
+    // 监控库存, 票档由不可购买变为可购买时发送通知
+    pub async fn monitor(
+        &self,
+        ticket_id: &String,
+        perform_id: &String,
+        ticket_name: &str,
+        perform_name: &str,
+    ) -> Result<bool> {
+        let times = self.account.ticket.pick_up_leaks.times;
+        let interval = self.account.ticket.pick_up_leaks.interval.max(1000);
+        let mut salable_skus: Vec<String> = Vec::new();
+
+        info!("监控模式, 开始查询库存...");
+        for i in 0..times {
+            self.event_sink.on_stock_check(i + 1);
+            if let Ok(perform_info) = self.get_perform_info(ticket_id, perform_id).await {
+                for sku in perform_info.perform.sku_list.iter() {
+                    let salable = sku.sku_salable.contains("true");
+                    let was_salable = salable_skus.contains(&sku.sku_id);
+                    if salable && !was_salable {
+                        logger::event(
+                            "sku_available",
+                            json!({"perform_id": perform_id, "sku_id": sku.sku_id, "sku_name": sku.price_name, "price": sku.price_value()}),
+                            &format!("票档:{}, 价格:{}, 有库存!", sku.price_name, sku.price_text()),
+                        );
+                        let message = NotifyMessage::available(
+                            &self.account.remark,
+                            ticket_name,
+                            perform_name,
+                            &sku.price_name,
+                            &sku.price_text(),
+                        );
+                        self.notify(&message).await;
+                        salable_skus.push(sku.sku_id.clone());
+                    } else if !salable && was_salable {
+                        salable_skus.retain(|id| id != &sku.sku_id);
+                    }
+                }
+            }
+            let wait = with_jitter(interval, self.account.ticket.pick_up_leaks.jitter_ms);
+            tokio::select! {
+                _ = self.stop_signal() => {
+                    return Ok(false);
+                }
+                _ = tokio::time::sleep(Duration::from_millis(wait)) => {}
+            }
+        }
+
+        Ok(false)
+    }
+
     // 轮询捡漏
-    pub async fn pick_up_leaks(&self, ticket_id: String, perform_id: String) -> Result<()> {
+    pub async fn pick_up_leaks(
+        &self,
+        ticket_id: String,
+        perform_id: String,
+    ) -> Result<Option<PurchasedTarget>> {
+        self.pick_up_leaks_multi(ticket_id, &[perform_id]).await
+    }
+
+    // 轮询多个场次捡漏, 依次查询各场次库存, 任一场次有票即购买
+    pub async fn pick_up_leaks_multi(
+        &self,
+        ticket_id: String,
+        perform_ids: &[String],
+    ) -> Result<Option<PurchasedTarget>> {
+        if perform_ids.is_empty() {
+            return Ok(None);
+        }
         let pick_up_leaks_times = self.account.ticket.pick_up_leaks.times;
         let mut pick_up_leaks_interval = self.account.ticket.pick_up_leaks.interval;
         if pick_up_leaks_interval < 1000 {
@@ -556,39 +1871,595 @@ impl DmTicket {
         }
 
         for i in 0..pick_up_leaks_times {
-            print!("\r\t第{}次查询库存, ", i + 1);
-            if let Ok(perform_info) = self.get_perform_info(&ticket_id, &perform_id).await {
-                for idx in 0..perform_info.perform.sku_list.len() {
-                    let sku = &perform_info.perform.sku_list[idx];
-                    let grade_idx = idx + 1;
-                    // 票挡有库存, 并且在配置中
-                    if sku.sku_salable.contains("true")
-                        && (pick_up_leaks_grades.is_empty()
-                            || pick_up_leaks_grades.contains(&grade_idx))
-                    {
-                        print!("有余票...");
-                        info!("票档:{}, 有库存, 去购买...", sku.price_name);
-                        if let Ok(res) = self
+            if self.is_purchased() {
+                return Ok(self.purchased_target());
+            }
+            let perform_id = &perform_ids[i as usize % perform_ids.len()];
+            let mut state = None;
+            let mut reported = false;
+            if let Ok(perform_info) = self.get_perform_info(&ticket_id, perform_id).await {
+                let sale_state = perform_info
+                    .perform
+                    .sale_state(self.sell_start_timestamp, self.time_sync.now_millis());
+                state = Some(sale_state);
+                // 未开售时不尝试购买
+                let skus = match sale_state {
+                    SaleState::NotOnSale => &[][..],
+                    _ => &perform_info.perform.sku_list[..],
+                };
+                // 按票档优先级依次检查, 多个票档同时有票时优先购买靠前的票档
+                for idx in leak_grade_order(&pick_up_leaks_grades, skus.len()) {
+                    let sku = &skus[idx];
+                    if sku.sku_salable.contains("true") {
+                        reported = true;
+                        self.event_sink.on_leak_check(i + 1, state, true);
+                        logger::event(
+                            "leak_found",
+                            json!({"perform_id": perform_info.perform.perform_id, "perform_name": perform_info.perform.perform_name, "sku_id": sku.sku_id, "sku_name": sku.price_name}),
+                            &tr(
+                                Msg::LeakFound,
+                                &[&perform_info.perform.perform_name, &sku.price_name],
+                            ),
+                        );
+                        let target = PurchasedTarget::new(&perform_info.perform.perform_name, sku);
+                        match self
                             .multiple_buy_attempts(
                                 &perform_info.perform.perform_id,
                                 &sku.sku_id,
                                 Some(pick_up_leaks_num),
+                                &target,
                             )
                             .await
                         {
-                            if res {
-                                return Ok(());
+                            Ok(true) => {
+                                info!(
+                                    "{}",
+                                    tr(
+                                        Msg::LeakSuccess,
+                                        &[&perform_info.perform.perform_name, &sku.price_name]
+                                    )
+                                );
+                                return Ok(self.purchased_target());
                             }
+                            Err(e) if is_cancelled(&e) => return Ok(None),
+                            Err(e) if should_stop(&e) => return Err(e),
+                            _ => {}
                         }
                         break;
                     }
                 }
             };
-            print!("无余票...");
-            let _ = io::stdout().flush();
-            tokio::time::sleep(Duration::from_millis(pick_up_leaks_interval)).await;
+            if !reported {
+                self.event_sink.on_leak_check(i + 1, state, false);
+            }
+            let interval = with_jitter(
+                pick_up_leaks_interval,
+                self.account.ticket.pick_up_leaks.jitter_ms,
+            );
+            tokio::select! {
+                _ = self.stop_signal() => {
+                    return Ok(None);
+                }
+                _ = tokio::time::sleep(Duration::from_millis(interval)) => {}
+            }
         }
 
-        Ok(())
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{
+        self, MockTransport, BUILD_ORDER_API, SESSION_EXPIRED_RET, SOLD_OUT_RET, SUBMIT_ORDER_API,
+    };
+
+    #[test]
+    fn effective_buy_num_limits() {
+        assert!(effective_buy_num(0, Some(2)).is_err());
+        assert_eq!(effective_buy_num(1, Some(2)).unwrap(), 1);
+        assert_eq!(effective_buy_num(1, None).unwrap(), 1);
+        // 超出限购数量时按限购数量购买
+        assert_eq!(effective_buy_num(4, Some(2)).unwrap(), 2);
+        // 未获取到限购数量或限购数量为0时不限制
+        assert_eq!(effective_buy_num(4, None).unwrap(), 4);
+        assert_eq!(effective_buy_num(4, Some(0)).unwrap(), 4);
+    }
+
+    #[test]
+    fn session_by_date() {
+        let perform_bases = [
+            testing::perform_base("2024-06-16 周日 19:30", &["p2"]),
+            testing::perform_base("2024-06-15 周六 19:30", &["p1"]),
+            testing::perform_base("2024-06-15 周六 14:00", &["p3"]),
+        ];
+
+        assert_eq!(find_session_by_date(&perform_bases, "2024-06-16").unwrap(), 1);
+        // 匹配多个时选择最早的场次
+        assert_eq!(find_session_by_date(&perform_bases, "2024-06-15").unwrap(), 3);
+        // 未匹配时列出可选场次
+        let e = find_session_by_date(&perform_bases, "2024-06-17").unwrap_err();
+        assert!(e.to_string().contains("2024-06-16 周日 19:30"));
+    }
+
+    #[test]
+    fn leak_grades_in_priority_order() {
+        // 未配置时按票档顺序
+        assert_eq!(leak_grade_order(&[], 3), vec![0, 1, 2]);
+        // 按配置的优先级顺序, 超出范围的票档跳过
+        assert_eq!(leak_grade_order(&[3, 1, 5, 0], 3), vec![2, 0]);
+    }
+
+    #[test]
+    fn jitter_within_range() {
+        // 未配置抖动时保持原间隔
+        assert_eq!(with_jitter(1000, 0), 1000);
+        for _ in 0..100 {
+            let interval = with_jitter(1000, 200);
+            assert!((800..=1200).contains(&interval), "{}", interval);
+        }
+        // 不会溢出
+        assert!(with_jitter(0, 200) <= 200);
+        assert!(with_jitter(u64::MAX, 200) >= u64::MAX - 200);
+    }
+
+    #[tokio::test]
+    async fn buy_success() {
+        let transport = MockTransport::new();
+        transport.push(BUILD_ORDER_API, testing::success(testing::order_info_data(1)));
+        transport.push(SUBMIT_ORDER_API, testing::success(testing::submit_data("1001")));
+        let dm_ticket = testing::dm_ticket(testing::account(), &transport).await;
+        let sku = testing::sku("1", "看台 680元");
+
+        let res = dm_ticket.buy_by_priority(&[sku.clone()]).await.unwrap();
+
+        assert_eq!(res.map(|target| target.sku_id), Some(sku.sku_id));
+        assert!(dm_ticket.is_purchased());
+        let order_result = dm_ticket.order_result().unwrap();
+        assert_eq!(order_result.order_id.as_deref(), Some("1001"));
+        assert_eq!(order_result.total_price.as_deref(), Some("680.00"));
+        assert_eq!(transport.calls(SUBMIT_ORDER_API), 1);
+
+        // 已下单成功时返回抢到的票档, 而不是空的票档
+        let res = dm_ticket
+            .buy_by_priority(&[testing::sku("2", "内场 1280元")])
+            .await
+            .unwrap();
+        assert_eq!(res.map(|target| target.sku_id), Some("1".to_string()));
+    }
+
+    #[tokio::test]
+    async fn submit_uses_configured_order_feature() {
+        let mut account = testing::account();
+        account.order_feature.sub_channel = "damai@custom".to_string();
+        account.order_feature.return_url = "https://example.com/pay".to_string();
+        let transport = MockTransport::new();
+        transport.push(BUILD_ORDER_API, testing::success(testing::order_info_data(1)));
+        transport.push(SUBMIT_ORDER_API, testing::success(testing::submit_data("1001")));
+        let dm_ticket = testing::dm_ticket(account, &transport).await;
+
+        dm_ticket
+            .buy_by_priority(&[testing::sku("1", "看台 680元")])
+            .await
+            .unwrap();
+
+        let form = transport.last_form(SUBMIT_ORDER_API).unwrap();
+        let data: Value = serde_json::from_str(form["data"].as_str().unwrap()).unwrap();
+        let feature: Value = serde_json::from_str(data["feature"].as_str().unwrap()).unwrap();
+        assert_eq!(feature["subChannel"], "damai@custom");
+        assert_eq!(feature["returnUrl"], "https://example.com/pay");
+    }
+
+    #[tokio::test]
+    async fn no_second_attempt_after_success() {
+        let transport = MockTransport::new();
+        transport.push(BUILD_ORDER_API, testing::success(testing::order_info_data(1)));
+        transport.push(SUBMIT_ORDER_API, testing::success(testing::submit_data("1001")));
+        let dm_ticket = testing::dm_ticket(testing::account(), &transport).await;
+        let (item_id, sku_id) = ("123456".to_string(), "1".to_string());
+        let target = PurchasedTarget::new("场次1", &testing::sku("1", "看台 680元"));
+
+        assert!(dm_ticket
+            .multiple_buy_attempts(&item_id, &sku_id, None, &target)
+            .await
+            .unwrap());
+        // 已下单成功, 再次抢购直接返回已有订单, 不再生成/提交订单
+        assert!(dm_ticket
+            .multiple_buy_attempts(&item_id, &sku_id, None, &target)
+            .await
+            .unwrap());
+
+        assert_eq!(transport.calls(BUILD_ORDER_API), 1);
+        assert_eq!(transport.calls(SUBMIT_ORDER_API), 1);
+        assert_eq!(dm_ticket.order_result().unwrap().order_id.as_deref(), Some("1001"));
+        assert_eq!(dm_ticket.purchased_target(), Some(target));
+    }
+
+    #[tokio::test]
+    async fn buy_sold_out() {
+        let transport = MockTransport::new();
+        transport.push(BUILD_ORDER_API, testing::dm_res(SOLD_OUT_RET, json!({})));
+        let dm_ticket = testing::dm_ticket(testing::account(), &transport).await;
+
+        let e = dm_ticket
+            .buy_by_priority(&[testing::sku("1", "看台 680元")])
+            .await
+            .unwrap_err();
+
+        assert_eq!(api_error(&e), Some(&DmApiError::ProductExpired));
+        assert!(!dm_ticket.is_purchased());
+        // 已售空不再重试, 也不提交订单
+        assert_eq!(transport.calls(BUILD_ORDER_API), 1);
+        assert_eq!(transport.calls(SUBMIT_ORDER_API), 0);
+    }
+
+    #[tokio::test]
+    async fn buy_session_expired() {
+        let transport = MockTransport::new();
+        transport.push(BUILD_ORDER_API, testing::dm_res(SESSION_EXPIRED_RET, json!({})));
+        let dm_ticket = testing::dm_ticket(testing::account(), &transport).await;
+
+        let e = dm_ticket
+            .buy_by_priority(&[testing::sku("1", "看台 680元")])
+            .await
+            .unwrap_err();
+
+        assert_eq!(api_error(&e), Some(&DmApiError::SessionExpired));
+        assert!(!dm_ticket.is_purchased());
+        assert_eq!(transport.calls(BUILD_ORDER_API), 1);
+        assert_eq!(transport.calls(SUBMIT_ORDER_API), 0);
+    }
+
+    #[tokio::test]
+    async fn deadline_stops_retrying() {
+        let transport = MockTransport::new();
+        for _ in 0..2 {
+            transport.push(BUILD_ORDER_API, testing::dm_res("FAIL_BIZ::其他错误", json!({})));
+        }
+        let mut dm_ticket = testing::dm_ticket(testing::account(), &transport).await;
+        dm_ticket.listen_ctrl_c = false;
+        // 已超出最长运行时间
+        dm_ticket.deadline = Some(tokio::time::Instant::now());
+        let target = PurchasedTarget::new("场次1", &testing::sku("1", "看台 680元"));
+
+        let e = dm_ticket
+            .multiple_buy_attempts(&"123456".to_string(), &"1".to_string(), None, &target)
+            .await
+            .unwrap_err();
+
+        assert!(is_cancelled(&e));
+        // 不再重试
+        assert_eq!(transport.calls(BUILD_ORDER_API), 1);
+    }
+
+    #[tokio::test]
+    async fn submit_timeout_not_resubmitted() {
+        let mut account = testing::account();
+        account.submit_retry_times = 2;
+        let transport = MockTransport::new();
+        for _ in 0..2 {
+            transport.push(BUILD_ORDER_API, testing::success(testing::order_info_data(1)));
+        }
+        transport.push_error(SUBMIT_ORDER_API, "模拟请求超时");
+        transport.push(SUBMIT_ORDER_API, testing::success(testing::submit_data("1001")));
+        let dm_ticket = testing::dm_ticket(account, &transport).await;
+
+        let e = dm_ticket
+            .buy_by_priority(&[testing::sku("1", "看台 680元")])
+            .await
+            .unwrap_err();
+
+        assert!(is_submit_uncertain(&e));
+        // 既不使用同一订单重试提交, 也不重新生成订单
+        assert_eq!(transport.calls(SUBMIT_ORDER_API), 1);
+        assert_eq!(transport.calls(BUILD_ORDER_API), 1);
+    }
+
+    // 记录抢购结果及异常事件
+    #[derive(Debug, Default)]
+    struct RecordingSink {
+        attempts: Mutex<Vec<(u8, bool)>>,
+        errors: Mutex<Vec<String>>,
+    }
+
+    impl EventSink for RecordingSink {
+        fn on_attempt(&self, attempt: u8, _latency: Duration, success: bool) {
+            self.attempts.lock().unwrap().push((attempt, success));
+        }
+
+        fn on_error(&self, error: &str) {
+            self.errors.lock().unwrap().push(error.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn events_report_attempts_and_errors() {
+        let transport = MockTransport::new();
+        transport.push(BUILD_ORDER_API, testing::success(testing::order_info_data(1)));
+        transport.push(SUBMIT_ORDER_API, testing::success(testing::submit_data("1001")));
+        let mut dm_ticket = testing::dm_ticket(testing::account(), &transport).await;
+        let sink = Arc::new(RecordingSink::default());
+        dm_ticket.event_sink = sink.clone();
+
+        dm_ticket
+            .buy_by_priority(&[testing::sku("1", "看台 680元")])
+            .await
+            .unwrap();
+        // 成功的抢购也上报
+        assert_eq!(*sink.attempts.lock().unwrap(), vec![(0, true)]);
+        assert!(sink.errors.lock().unwrap().is_empty());
+
+        let transport = MockTransport::new();
+        transport.push(BUILD_ORDER_API, testing::success(testing::order_info_data(1)));
+        transport.push_error(SUBMIT_ORDER_API, "模拟请求超时");
+        let mut dm_ticket = testing::dm_ticket(testing::account(), &transport).await;
+        let sink = Arc::new(RecordingSink::default());
+        dm_ticket.event_sink = sink.clone();
+
+        assert!(dm_ticket
+            .buy_by_priority(&[testing::sku("1", "看台 680元")])
+            .await
+            .is_err());
+        assert_eq!(sink.errors.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn parallel_keeps_product_expired() {
+        let mut account = testing::account();
+        account.parallel_builds = 2;
+        account.parallel_stagger = 0;
+        let transport = MockTransport::new();
+        transport.push(BUILD_ORDER_API, testing::dm_res(SOLD_OUT_RET, json!({})));
+        transport.push_error(BUILD_ORDER_API, "模拟请求超时");
+        let dm_ticket = testing::dm_ticket(account, &transport).await;
+        let (item_id, sku_id) = ("123456".to_string(), "1".to_string());
+
+        // 另一流程的网络错误/重试耗尽不会掩盖商品信息过期
+        let e = dm_ticket
+            .multiple_buy_attempts_parallel(&item_id, &sku_id, None, &PurchasedTarget::default())
+            .await
+            .unwrap_err();
+
+        assert_eq!(api_error(&e), Some(&DmApiError::ProductExpired));
+        assert_eq!(transport.calls(SUBMIT_ORDER_API), 0);
+    }
+
+    #[tokio::test]
+    async fn all_real_names_selects_every_viewer() {
+        let mut account = testing::account();
+        account.ticket.all_real_names = true;
+        let dm_ticket = testing::dm_ticket(account, &MockTransport::new()).await;
+
+        let order_data = dm_ticket.build_order_data(&testing::order_info(4)).unwrap();
+
+        assert_eq!(testing::used_viewers(&order_data), vec![1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn offline_token_expired_not_refreshed() {
+        let transport = MockTransport::new();
+        transport.push(
+            BUILD_ORDER_API,
+            testing::dm_res("FAIL_SYS_TOKEN_EXOIRED::令牌过期", json!({})),
+        );
+        let dm_ticket = testing::dm_ticket(testing::account(), &transport).await;
+
+        // 离线模式不请求网络刷新token, 也不重发请求
+        let res = dm_ticket
+            .build_order(&"123456".to_string(), &"1".to_string(), 1, 0)
+            .await;
+
+        assert!(res.is_err());
+        assert_eq!(transport.calls(BUILD_ORDER_API), 1);
+        assert_eq!(dm_ticket.get_user_info().await.unwrap().user_id, 0);
+    }
+
+    // 跨票档凑票的测试账号, 共需购买3张
+    fn split_account() -> Account {
+        let mut account = testing::account();
+        account.ticket.num = 3;
+        account.ticket.split_across_grades = true;
+        account
+    }
+
+    #[tokio::test]
+    async fn split_across_grades_allocation() {
+        let transport = MockTransport::new();
+        // 看台: 3张库存不足, 2张成功, 剩余1张时已售空; 内场: 1张成功
+        transport.push(BUILD_ORDER_API, testing::success(testing::order_info_data(3)));
+        transport.push(SUBMIT_ORDER_API, testing::dm_res("FAIL_BIZ_STOCK::库存不足", json!({})));
+        transport.push(BUILD_ORDER_API, testing::success(testing::order_info_data(3)));
+        transport.push(SUBMIT_ORDER_API, testing::success(testing::submit_data("1001")));
+        transport.push(BUILD_ORDER_API, testing::dm_res(SOLD_OUT_RET, json!({})));
+        transport.push(BUILD_ORDER_API, testing::success(testing::order_info_data(3)));
+        transport.push(SUBMIT_ORDER_API, testing::success(testing::submit_data("1002")));
+        let dm_ticket = testing::dm_ticket(split_account(), &transport).await;
+
+        let target = dm_ticket
+            .buy_by_priority(&[testing::sku("1", "看台 680元"), testing::sku("2", "内场 1280元")])
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(target.sku_name, "看台 680元×2 + 内场 1280元×1");
+        assert!(dm_ticket.is_purchased());
+        assert_eq!(dm_ticket.purchased_target(), Some(target));
+        assert_eq!(transport.calls(BUILD_ORDER_API), 4);
+        assert_eq!(transport.calls(SUBMIT_ORDER_API), 3);
+    }
+
+    #[tokio::test]
+    async fn split_across_grades_stops_on_product_expired() {
+        let transport = MockTransport::new();
+        transport.push(BUILD_ORDER_API, testing::dm_res(SOLD_OUT_RET, json!({})));
+        transport.push(BUILD_ORDER_API, testing::success(testing::order_info_data(3)));
+        transport.push(SUBMIT_ORDER_API, testing::success(testing::submit_data("1001")));
+        let dm_ticket = testing::dm_ticket(split_account(), &transport).await;
+
+        // 看台已售空时不减少数量重试, 直接尝试内场
+        let target = dm_ticket
+            .buy_by_priority(&[testing::sku("1", "看台 680元"), testing::sku("2", "内场 1280元")])
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(target.sku_name, "内场 1280元×3");
+        assert_eq!(transport.calls(BUILD_ORDER_API), 2);
+    }
+
+    #[tokio::test]
+    async fn split_across_grades_stops_without_order_id() {
+        let transport = MockTransport::new();
+        transport.push(BUILD_ORDER_API, testing::success(testing::order_info_data(3)));
+        transport.push(SUBMIT_ORDER_API, testing::dm_res("FAIL_BIZ_STOCK::库存不足", json!({})));
+        transport.push(BUILD_ORDER_API, testing::success(testing::order_info_data(3)));
+        transport.push(SUBMIT_ORDER_API, testing::success(json!({})));
+        let dm_ticket = testing::dm_ticket(split_account(), &transport).await;
+
+        // 无法确认已购买数量时停止, 不再提交剩余数量的订单
+        let target = dm_ticket
+            .buy_by_priority(&[testing::sku("1", "看台 680元"), testing::sku("2", "内场 1280元")])
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(target.sku_name, "看台 680元×2");
+        assert!(dm_ticket.is_purchased());
+        assert_eq!(transport.calls(SUBMIT_ORDER_API), 2);
+    }
+
+    #[test]
+    fn viewer_count_selection() {
+        // 数量为0
+        assert!(is_invalid_viewers(&viewer_count_indices(0, &[], 3).unwrap_err()));
+        // 范围内, 未配置real_names时选择前viewer_count位, 否则选择real_names中的前viewer_count位
+        assert_eq!(viewer_count_indices(2, &[], 3).unwrap(), vec![1, 2]);
+        assert_eq!(viewer_count_indices(3, &[], 3).unwrap(), vec![1, 2, 3]);
+        assert_eq!(viewer_count_indices(2, &[3, 1, 2], 3).unwrap(), vec![3, 1]);
+        // 超出账号的实名观演人数量
+        assert!(is_invalid_viewers(&viewer_count_indices(4, &[], 3).unwrap_err()));
+        assert!(is_invalid_viewers(&viewer_count_indices(1, &[4], 3).unwrap_err()));
+        assert!(is_invalid_viewers(&viewer_count_indices(2, &[1], 3).unwrap_err()));
+    }
+
+    #[tokio::test]
+    async fn viewer_count_out_of_range_not_retried() {
+        let mut account = testing::account();
+        account.ticket.viewer_count = Some(2);
+        let transport = MockTransport::new();
+        for _ in 0..2 {
+            transport.push(BUILD_ORDER_API, testing::success(testing::order_info_data(1)));
+        }
+        let dm_ticket = testing::dm_ticket(account, &transport).await;
+
+        let e = dm_ticket
+            .buy_by_priority(&[testing::sku("1", "看台 680元")])
+            .await
+            .unwrap_err();
+
+        assert!(is_invalid_viewers(&e));
+        assert_eq!(transport.calls(BUILD_ORDER_API), 1);
+        assert_eq!(transport.calls(SUBMIT_ORDER_API), 0);
+    }
+
+    #[tokio::test]
+    async fn select_skus_skips_unavailable_sessions() {
+        let mut account = testing::account();
+        account.ticket.grade_priority = vec![5, 1];
+        let dm_ticket = testing::dm_ticket(account, &MockTransport::new()).await;
+        let perform_bases = [testing::perform_base("选座场", &[]), testing::perform_base("第二场", &["p2"])];
+        dm_ticket.perform_info_cache.insert(
+            "p2",
+            testing::perform_info("p2", vec![testing::sku("1", "看台 680元"), testing::sku("2", "内场 1280元")]),
+        );
+        let ticket_id = "123456".to_string();
+
+        // 超出范围的场次/没有可购买演出的场次/超出范围的票档跳过
+        let (skus, perform_ids, _) = dm_ticket
+            .select_skus(&ticket_id, &perform_bases, &[3, 1, 2])
+            .await
+            .unwrap();
+        assert_eq!(skus.iter().map(|sku| sku.sku_id.as_str()).collect::<Vec<_>>(), vec!["1"]);
+        assert_eq!(perform_ids, vec!["p2"]);
+
+        // 所有场次都不可用时返回原因, 不会panic
+        let e = dm_ticket
+            .select_skus(&ticket_id, &perform_bases, &[1])
+            .await
+            .unwrap_err();
+        assert_eq!(e.to_string(), tr(Msg::SessionNoPerform, &[&1, &"选座场"]));
+    }
+
+    #[tokio::test]
+    async fn select_skus_by_max_price() {
+        let mut account = testing::account();
+        account.ticket.max_price = Some(1000.0);
+        let dm_ticket = testing::dm_ticket(account, &MockTransport::new()).await;
+        let perform_bases = [testing::perform_base("第一场", &["p1"]), testing::perform_base("第二场", &["p2"])];
+        dm_ticket
+            .perform_info_cache
+            .insert("p1", testing::perform_info("p1", vec![testing::sku("1", "内场 1280元")]));
+        dm_ticket.perform_info_cache.insert(
+            "p2",
+            testing::perform_info(
+                "p2",
+                vec![
+                    testing::sku("2", "看台 380元"),
+                    testing::sku("3", "看台 680元"),
+                    testing::sku("4", "内场 1280元"),
+                ],
+            ),
+        );
+        let ticket_id = "123456".to_string();
+
+        // 第一场没有不超过价格上限的票档, 选择第二场不超过价格上限的最高价票档
+        let (skus, perform_ids, _) = dm_ticket
+            .select_skus(&ticket_id, &perform_bases, &[1, 2])
+            .await
+            .unwrap();
+        assert_eq!(skus.iter().map(|sku| sku.sku_id.as_str()).collect::<Vec<_>>(), vec!["3"]);
+        assert_eq!(perform_ids, vec!["p2"]);
+
+        // 所有场次都没有符合条件的票档
+        assert!(dm_ticket
+            .select_skus(&ticket_id, &perform_bases, &[1])
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn clock_skew_logged_once_per_run() {
+        let transport = MockTransport::new();
+        for _ in 0..2 {
+            transport.push(BUILD_ORDER_API, testing::dm_res(SOLD_OUT_RET, json!({})));
+        }
+        let dm_ticket = testing::dm_ticket(testing::account(), &transport).await;
+        let target = PurchasedTarget::new("场次1", &testing::sku("1", "看台 680元"));
+
+        assert!(!dm_ticket.clock_skew_logged.load(Ordering::SeqCst));
+        let _ = dm_ticket.buy(&"123456".to_string(), &"1".to_string(), 1, 0, &target).await;
+        assert!(dm_ticket.clock_skew_logged.load(Ordering::SeqCst));
+
+        // 刷新会话后重新运行, 重置后再次记录
+        dm_ticket.begin_run();
+        assert!(!dm_ticket.clock_skew_logged.load(Ordering::SeqCst));
+        let _ = dm_ticket.buy(&"123456".to_string(), &"1".to_string(), 1, 1, &target).await;
+        assert!(dm_ticket.clock_skew_logged.load(Ordering::SeqCst));
+        assert_eq!(dm_ticket.stats().attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn viewers_fewer_than_num() {
+        let mut account = testing::account();
+        account.ticket.num = 3;
+        let dm_ticket = testing::dm_ticket(account, &MockTransport::new()).await;
+
+        let order_data = dm_ticket.build_order_data(&testing::order_info(1)).unwrap();
+
+        // 只选择已有的观演人, 不会补充空的观演人
+        assert_eq!(testing::used_viewers(&order_data), vec![1]);
+        let viewer_list = order_data["dmViewer_1"]["fields"]["viewerList"].as_array().unwrap();
+        assert_eq!(viewer_list.len(), 1);
     }
 }
@@ -0,0 +1,87 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use log::info;
+use std::{
+    net::UdpSocket,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// NTP时间戳(1900年起)与Unix时间戳(1970年起)的差值, 单位秒
+const NTP_UNIX_EPOCH_DELTA: i64 = 2_208_988_800;
+
+// NTP请求超时时间
+const NTP_TIMEOUT: Duration = Duration::from_secs(3);
+
+// 时间校准, 记录本地时间与标准时间的偏移量
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeSync {
+    // 偏移量, 单位毫秒。标准时间 = 本地时间 + offset
+    offset: i64,
+}
+
+impl TimeSync {
+    pub fn new(offset: i64) -> Self {
+        Self { offset }
+    }
+
+    // 通过NTP服务器计算时间偏移量
+    pub async fn from_ntp(server: &str) -> Result<Self> {
+        let server = match server.contains(':') {
+            true => server.to_string(),
+            false => format!("{}:123", server),
+        };
+        let offset = tokio::task::spawn_blocking(move || ntp_offset(&server)).await??;
+        info!("NTP时间校准完成, 本地时间偏移:{}毫秒", offset);
+        Ok(Self { offset })
+    }
+
+    // 偏移量, 单位毫秒
+    pub fn offset_millis(&self) -> i64 {
+        self.offset
+    }
+
+    // 校准后的当前时间戳, 单位毫秒
+    pub fn now_millis(&self) -> i64 {
+        let local: DateTime<Local> = Local::now();
+        local.timestamp_millis() + self.offset
+    }
+}
+
+// 当前Unix时间戳, 单位毫秒
+fn unix_millis() -> Result<i64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64)
+}
+
+// NTP时间戳转Unix时间戳, 单位毫秒
+fn ntp_to_unix_millis(bytes: &[u8]) -> i64 {
+    let secs = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64;
+    let frac = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as i64;
+    let millis = (frac * 1000) >> 32;
+    (secs - NTP_UNIX_EPOCH_DELTA) * 1000 + millis
+}
+
+// 发送SNTP请求, 计算本地时间偏移量
+fn ntp_offset(server: &str) -> Result<i64> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.set_read_timeout(Some(NTP_TIMEOUT))?;
+    socket.connect(server)?;
+
+    // LI=0, VN=3, Mode=3(client)
+    let mut packet = [0u8; 48];
+    packet[0] = 0x1b;
+
+    let t0 = unix_millis()?;
+    socket.send(&packet)?;
+    let size = socket.recv(&mut packet)?;
+    let t3 = unix_millis()?;
+
+    if size < 48 || packet[40..44] == [0u8; 4] {
+        return Err(anyhow!("NTP服务器:{}返回数据异常", server));
+    }
+
+    // 服务器接收/发送时间
+    let t1 = ntp_to_unix_millis(&packet[32..40]);
+    let t2 = ntp_to_unix_millis(&packet[40..48]);
+
+    Ok(((t1 - t0) + (t2 - t3)) / 2)
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::{self, Stdout},
+    sync::Mutex,
+    time::Duration,
+};
+
+use anyhow::Result;
+use crossterm::{
+    cursor,
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Row, Table},
+    Terminal,
+};
+use log::Record;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    events::{countdown_text, leak_check_text, EventSink},
+    i18n::{tr, Msg},
+    logger::ACCOUNT_REMARK,
+    models::perform::SaleState,
+    ticket::PurchasedTarget,
+};
+
+// 刷新间隔
+const REFRESH_INTERVAL: Duration = Duration::from_millis(200);
+
+// 显示的最近日志行数
+const LOG_LINES: usize = 8;
+
+// 单个账号的实时状态
+#[derive(Debug, Clone, Default)]
+struct AccountStatus {
+    // 距离开抢的剩余毫秒数
+    countdown_ms: Option<i64>,
+    // 已尝试的抢购次数
+    attempts: u32,
+    // 最近一次抢购的耗时
+    latency: Option<Duration>,
+    // 最近的状态
+    status: String,
+}
+
+// 终端仪表盘, 接收各账号的状态事件并实时刷新显示, 不影响抢购逻辑
+#[derive(Debug, Default)]
+pub struct TuiSink {
+    accounts: Mutex<BTreeMap<String, AccountStatus>>,
+    // 最近的日志, 仪表盘显示期间日志转发到这里, 避免打乱画面
+    logs: Mutex<VecDeque<String>>,
+}
+
+impl TuiSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 更新当前任务所属账号的状态
+    fn update(&self, f: impl FnOnce(&mut AccountStatus)) {
+        let remark = ACCOUNT_REMARK
+            .try_with(|remark| remark.clone())
+            .unwrap_or_default();
+        f(self.accounts.lock().unwrap().entry(remark).or_default());
+    }
+
+    // 记录一条日志, 只保留最近LOG_LINES条
+    pub fn on_log(&self, record: &Record) {
+        let line = match ACCOUNT_REMARK.try_with(|remark| remark.clone()) {
+            Ok(remark) => format!("{:<5} [{}] {}", record.level(), remark, record.args()),
+            Err(_) => format!("{:<5} {}", record.level(), record.args()),
+        };
+        let mut logs = self.logs.lock().unwrap();
+        if logs.len() >= LOG_LINES {
+            logs.pop_front();
+        }
+        logs.push_back(line);
+    }
+
+    // 生成表格行
+    fn rows(&self) -> Vec<Row<'static>> {
+        self.accounts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(remark, status)| {
+                Row::new(vec![
+                    remark.clone(),
+                    status
+                        .countdown_ms
+                        .map(countdown_text)
+                        .unwrap_or_else(|| "-".to_string()),
+                    status.attempts.to_string(),
+                    status
+                        .latency
+                        .map(|latency| tr(Msg::Millis, &[&latency.as_millis()]))
+                        .unwrap_or_else(|| "-".to_string()),
+                    status.status.clone(),
+                ])
+            })
+            .collect()
+    }
+
+    // 显示仪表盘直到stop被取消, 使用备用屏幕, 退出后恢复终端
+    pub async fn run_dashboard(&self, stop: CancellationToken) -> Result<()> {
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen, cursor::Hide)?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+        let res = self.render_loop(&mut terminal, &stop).await;
+
+        execute!(terminal.backend_mut(), LeaveAlternateScreen, cursor::Show)?;
+        res
+    }
+
+    async fn render_loop(
+        &self,
+        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+        stop: &CancellationToken,
+    ) -> Result<()> {
+        loop {
+            let rows = self.rows();
+            let logs = Vec::from(self.logs.lock().unwrap().clone()).join("\n");
+            terminal.draw(|frame| {
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Min(5),
+                        Constraint::Length(LOG_LINES as u16 + 2),
+                    ])
+                    .split(frame.size());
+                let table = Table::new(rows)
+                    .header(
+                        Row::new(
+                            [Msg::TuiAccount, Msg::TuiCountdown, Msg::TuiAttempts, Msg::TuiLatency, Msg::TuiStatus]
+                                .map(|msg| tr(msg, &[])),
+                        )
+                            .style(Style::default().add_modifier(Modifier::BOLD)),
+                    )
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(tr(Msg::TuiTitle, &[])),
+                    )
+                    .widths(&[
+                        Constraint::Percentage(15),
+                        Constraint::Percentage(25),
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(40),
+                    ]);
+                frame.render_widget(table, chunks[0]);
+                let logs = Paragraph::new(logs).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(tr(Msg::TuiLogs, &[])),
+                );
+                frame.render_widget(logs, chunks[1]);
+            })?;
+            tokio::select! {
+                _ = stop.cancelled() => return Ok(()),
+                _ = tokio::time::sleep(REFRESH_INTERVAL) => {}
+            }
+        }
+    }
+}
+
+impl EventSink for TuiSink {
+    fn on_countdown(&self, remaining_ms: i64) {
+        self.update(|status| {
+            status.countdown_ms = Some(remaining_ms);
+            status.status = tr(Msg::StatusWaiting, &[]);
+        });
+    }
+
+    fn on_attempt(&self, attempt: u8, latency: Duration, success: bool) {
+        self.update(|status| {
+            status.countdown_ms = None;
+            status.attempts += 1;
+            status.latency = Some(latency);
+            let msg = if success {
+                Msg::StatusAttemptSuccess
+            } else {
+                Msg::StatusRetrying
+            };
+            status.status = tr(msg, &[&(u32::from(attempt) + 1)]);
+        });
+    }
+
+    fn on_stock_check(&self, round: u64) {
+        self.update(|status| status.status = tr(Msg::StatusStockCheck, &[&round]));
+    }
+
+    fn on_leak_check(&self, round: u64, state: Option<SaleState>, found: bool) {
+        self.update(|status| {
+            status.countdown_ms = None;
+            status.status = tr(Msg::StatusLeakCheck, &[&round, &leak_check_text(state, found)]);
+        });
+    }
+
+    fn on_payment_link(&self, _link: &str) {
+        self.update(|status| status.status = tr(Msg::StatusSubmitted, &[]));
+    }
+
+    fn on_success(&self, target: &PurchasedTarget) {
+        self.update(|status| {
+            status.status = tr(Msg::StatusSuccess, &[&target.perform_name, &target.sku_name])
+        });
+    }
+
+    fn on_status(&self, message: &str) {
+        self.update(|status| status.status = message.to_string());
+    }
+
+    fn on_error(&self, error: &str) {
+        self.update(|status| status.status = error.to_string());
+    }
+}